@@ -1,159 +1,380 @@
-use gtk4::{Application, ApplicationWindow, Box, Label, Orientation, prelude::*};
-use glib::{MainContext, timeout_add_local, ControlFlow};
-use std::{rc::Rc, cell::RefCell, sync::mpsc, time::Duration};
-use tray::Tray;
+use gtk4::{Application, ApplicationWindow, Box, CenterBox, CssProvider, Orientation, gdk::{Display, Monitor}, gio::{ApplicationHoldGuard, FileMonitor}, prelude::*};
+use glib::MainContext;
+use std::{rc::Rc, cell::RefCell, path::{Path, PathBuf}, time::Duration};
 
-use crate::config::BarConfig;
-use crate::ui::{load_css, setup_window};
-use crate::ui::components::{WorkspacesComponent, TrayComponent, ClockComponent, LangComponent};
-use crate::services::start_hyprland_event_listener;
+use crate::config::{BarConfig, default_config_path};
+use crate::ui::{apply_icon_theme, apply_window_config, css_path, load_css, reload_css, setup_window};
+use crate::ui::components::{BoxedModule, ModuleContext, build_module};
+use crate::services::{HyprlandEvent, HyprlandEvents, Worker, start_hyprland_event_listener, watch_dirs, watch_file};
+
+/// Пауза после изменений в каталогах иконок: пакеты устанавливают файлы сериями
+const ICON_REFRESH_DELAY: Duration = Duration::from_secs(2);
 
 /// Основная логика приложения
 pub struct BarApp {
+    config_path: Option<PathBuf>,
+    state: Rc<RefCell<BarState>>,
+}
+
+/// Изменяемое состояние приложения, общее для UI и наблюдателей за файлами
+struct BarState {
     config: BarConfig,
+    app: Option<Application>,
+    /// Удерживает приложение запущенным, пока нет ни одного монитора
+    hold: Option<ApplicationHoldGuard>,
+    bars: Vec<Bar>,
+    css: Option<CssProvider>,
+    css_path: Option<PathBuf>,
+    css_watcher: Option<FileMonitor>,
+    config_watcher: Option<FileMonitor>,
+    /// Мониторы каталогов иконок для обновления индекса
+    icon_watchers: Vec<FileMonitor>,
+    hyprland_events: HyprlandEvents,
+    /// Фоновый поток для запросов к Hyprland, общий для всех баров
+    worker: Worker,
+    /// Отдельный поток для перестройки индекса иконок: обход каталогов
+    /// занимает секунды и не должен задерживать запросы к Hyprland
+    icon_worker: Worker,
+}
+
+/// Бар на одном мониторе: layer-shell окно и его содержимое
+struct Bar {
+    monitor: Monitor,
+    window: ApplicationWindow,
+    content: Option<BarContent>,
+}
+
+impl Bar {
+    /// Применяет конфигурацию, пересобирая содержимое только при изменении раскладки
+    fn apply_config(&mut self, config: &BarConfig, hyprland_events: &HyprlandEvents, worker: &Worker) {
+        apply_window_config(&self.window, config, &self.monitor);
+
+        match &mut self.content {
+            Some(content) if content.same_layout(config) => content.apply_config(config),
+            _ => {
+                // Старое содержимое удаляется первым, чтобы его таймеры остановились
+                self.content = None;
+                let content = BarContent::build(config, &self.monitor, hyprland_events.clone(), worker.clone());
+                self.window.set_child(Some(&content.root));
+                self.content = Some(content);
+            }
+        }
+    }
+}
+
+/// Содержимое окна: корневой контейнер и запущенные в нем модули.
+///
+/// При удалении модули останавливают свои таймеры, поэтому содержимое можно
+/// пересобрать, не пересоздавая layer-shell окно.
+struct BarContent {
+    root: CenterBox,
+    modules: Vec<BoxedModule>,
+    ctx: ModuleContext,
+}
+
+impl BarContent {
+    fn build(config: &BarConfig, monitor: &Monitor, hyprland_events: HyprlandEvents, worker: Worker) -> Self {
+        let ctx = ModuleContext {
+            config: config.clone(),
+            monitor: monitor.connector().map(|connector| connector.to_string()),
+            hyprland_events,
+            worker,
+        };
+
+        let orientation = ctx.orientation();
+        let root = CenterBox::new();
+        root.set_orientation(orientation);
+        root.add_css_class("bar");
+        root.add_css_class(if orientation == Orientation::Vertical { "vertical" } else { "horizontal" });
+        root.set_hexpand(true);
+        root.set_halign(gtk4::Align::Fill);
+        root.set_vexpand(true);
+        root.set_valign(gtk4::Align::Fill);
+
+        let mut modules = Vec::new();
+        let sections = [
+            ("modules-left", &config.modules_left),
+            ("modules-center", &config.modules_center),
+            ("modules-right", &config.modules_right),
+        ]
+        .map(|(class, names)| {
+            let section = Box::new(orientation, config.spacing);
+            section.add_css_class(class);
+            for name in names {
+                if let Some(mut module) = build_module(name, &ctx) {
+                    section.append(&module.widget());
+                    module.start(&ctx);
+                    modules.push(module);
+                }
+            }
+            section
+        });
+
+        let [left, center, right] = sections;
+        root.set_start_widget(Some(&left));
+        root.set_center_widget(Some(&center));
+        root.set_end_widget(Some(&right));
+
+        Self { root, modules, ctx }
+    }
+
+    /// Можно ли применить конфигурацию к уже созданным модулям,
+    /// не пересобирая секции
+    fn same_layout(&self, config: &BarConfig) -> bool {
+        let current = &self.ctx.config;
+        current.modules_left == config.modules_left
+            && current.modules_center == config.modules_center
+            && current.modules_right == config.modules_right
+            && current.spacing == config.spacing
+            && current.position.is_vertical() == config.position.is_vertical()
+    }
+
+    /// Передает новую конфигурацию всем модулям
+    fn apply_config(&mut self, config: &BarConfig) {
+        self.ctx.config = config.clone();
+        for module in &mut self.modules {
+            module.on_config(&self.ctx);
+        }
+    }
+
+    /// Сообщает модулям, что индекс иконок перестроен
+    fn reload_icons(&mut self) {
+        for module in &mut self.modules {
+            module.on_icons_changed(&self.ctx);
+        }
+    }
 }
 
 impl BarApp {
-    pub fn new() -> Self {
+    pub fn new(config: BarConfig, config_path: Option<PathBuf>) -> Self {
         Self {
-            config: BarConfig::new(),
+            config_path,
+            state: Rc::new(RefCell::new(BarState {
+                config,
+                app: None,
+                hold: None,
+                bars: Vec::new(),
+                css: None,
+                css_path: None,
+                css_watcher: None,
+                config_watcher: None,
+                icon_watchers: Vec::new(),
+                hyprland_events: HyprlandEvents::default(),
+                worker: Worker::spawn("oxidbar-worker"),
+                icon_worker: Worker::spawn("oxidbar-icon-refresh"),
+            })),
         }
     }
 
+    /// Создает бары при первой активации приложения.
+    ///
+    /// Повторный запуск активирует уже работающий экземпляр: тогда
+    /// существующие бары только показываются, а слушатель событий,
+    /// стили и наблюдатели не создаются заново.
     pub fn build_ui(&self, app: &Application) {
-        load_css();
+        if self.state.borrow().app.is_some() {
+            for bar in &self.state.borrow().bars {
+                bar.window.present();
+            }
+            return;
+        }
+
+        {
+            let mut state = self.state.borrow_mut();
+            state.app = Some(app.clone());
+            state.hold = Some(app.hold());
+
+            let css_path = css_path(&state.config);
+            state.css = Some(load_css(css_path.as_deref()));
+            state.css_watcher = css_path.as_deref().and_then(|path| Self::watch_css(&self.state, path));
+            state.css_path = css_path;
+
+            helpers::start_icon_index();
+            state.icon_watchers = Self::watch_icons(&self.state);
+
+            // Запуск Hyprland event listener; события доставляются в главный цикл по мере поступления
+            let (tx, rx) = async_channel::unbounded::<HyprlandEvent>();
+            start_hyprland_event_listener(tx);
+            state.hyprland_events.attach(rx);
+
+            let config_path = self.config_path.clone().or_else(default_config_path);
+            if let Some(path) = config_path {
+                let weak = Rc::downgrade(&self.state);
+                let watched_path = path.clone();
+                state.config_watcher = watch_file(&path, move || {
+                    if let Some(state) = weak.upgrade() {
+                        Self::reload_config(&state, &watched_path);
+                    }
+                });
+            }
+        }
+
+        // Бар на каждом мониторе; при подключении и отключении дисплеев список обновляется
+        if let Some(display) = Display::default() {
+            let weak = Rc::downgrade(&self.state);
+            display.monitors().connect_items_changed(move |_, _, _, _| {
+                if let Some(state) = weak.upgrade() {
+                    Self::sync_bars(&state);
+                }
+            });
+        }
+        Self::sync_bars(&self.state);
+    }
+
+    /// Создает бары для новых мониторов и удаляет бары отключенных
+    fn sync_bars(state_rc: &Rc<RefCell<BarState>>) {
+        let Some(display) = Display::default() else {
+            return;
+        };
+        let monitors = display.monitors();
+        let monitors: Vec<Monitor> = (0..monitors.n_items())
+            .filter_map(|i| monitors.item(i))
+            .filter_map(|obj| obj.downcast::<Monitor>().ok())
+            .collect();
+
+        let mut state = state_rc.borrow_mut();
+        let state = &mut *state;
+        let Some(app) = state.app.clone() else {
+            return;
+        };
+
+        let config = &state.config;
+        // Масштаб мониторов мог измениться
+        apply_icon_theme(config);
+        state.bars.retain(|bar| {
+            let keep = monitors.contains(&bar.monitor)
+                && config.monitor_enabled(bar.monitor.connector().as_deref());
+            if !keep {
+                logger::log_info("BarApp::sync_bars", format!("Removing bar from {:?}", bar.monitor.connector()));
+                bar.window.destroy();
+            }
+            keep
+        });
 
+        for monitor in monitors {
+            let connector = monitor.connector();
+            if !config.monitor_enabled(connector.as_deref())
+                || state.bars.iter().any(|bar| bar.monitor == monitor)
+            {
+                continue;
+            }
+            logger::log_info("BarApp::sync_bars", format!("Creating bar on {connector:?}"));
+            let bar = Self::build_bar(&app, config, &monitor, &state.hyprland_events, &state.worker);
+            state.bars.push(bar);
+        }
+    }
+
+    fn build_bar(
+        app: &Application,
+        config: &BarConfig,
+        monitor: &Monitor,
+        hyprland_events: &HyprlandEvents,
+        worker: &Worker,
+    ) -> Bar {
         let window = ApplicationWindow::builder()
             .application(app)
             .title("OxidBar")
             .build();
-        
-        setup_window(&window, &self.config);
 
-        let root = Box::new(Orientation::Horizontal, self.config.spacing);
-        root.add_css_class("bar");
-        root.set_hexpand(true);
-        root.set_halign(gtk4::Align::Fill);
+        setup_window(&window, config, monitor);
 
-        // Workspaces
-        let workspaces_box = Box::new(Orientation::Horizontal, self.config.spacing);
-        let workspaces_component = Rc::new(RefCell::new(WorkspacesComponent::new(
-            workspaces_box.clone(),
-            self.config.clone(),
-        )));
-        root.append(&workspaces_box);
-
-        // Spacer
-        let spacer = Box::new(Orientation::Horizontal, 0);
-        spacer.set_hexpand(true);
-        root.append(&spacer);
-
-        // Tray
-        let tray_box = Box::new(Orientation::Horizontal, 6);
-        root.append(&tray_box);
-
-        // Lang
-        let lang_label = Label::new(None);
-        let lang_component = Rc::new(RefCell::new(LangComponent::new(lang_label.clone())));
-        root.append(&lang_label);
-
-        // Clock
-        let clock_label = Label::new(None);
-        let clock_component = Rc::new(RefCell::new(ClockComponent::new(clock_label.clone())));
-        root.append(&clock_label);
-
-        window.set_child(Some(&root));
+        let content = BarContent::build(config, monitor, hyprland_events.clone(), worker.clone());
+        window.set_child(Some(&content.root));
         window.present();
 
-        // Инициализация компонентов
-        workspaces_component.borrow().refresh();
-        lang_component.borrow().update();
-        clock_component.borrow().update();
+        Bar {
+            monitor: monitor.clone(),
+            window,
+            content: Some(content),
+        }
+    }
 
-        // Запуск Hyprland event listener
-        let (tx, rx) = mpsc::channel();
-        start_hyprland_event_listener(tx);
+    /// Перечитывает конфигурацию и применяет изменения без пересоздания окон.
+    ///
+    /// При ошибке разбора сохраняется текущая конфигурация.
+    fn reload_config(state_rc: &Rc<RefCell<BarState>>, path: &Path) {
+        let config = match BarConfig::from_file(path) {
+            Ok(config) => config,
+            Err(e) => {
+                logger::log_error("BarApp::reload_config", format!("{e:#}"));
+                return;
+            }
+        };
 
-        // Инициализация tray
-        let tray_box_clone = tray_box.clone();
-        let root_clone = root.clone();
-        let config_clone = self.config.clone();
-        MainContext::default().spawn_local(async move {
-            match Tray::new().await {
-                Ok(tray) => {
-                    let tray_rc = Rc::new(RefCell::new(tray));
-                    let tray_component = TrayComponent::new(
-                        tray_box_clone.clone(),
-                        root_clone.clone(),
-                        config_clone.clone(),
-                        tray_rc.clone(),
-                    );
-                    Self::start_tray_updater(tray_box_clone, root_clone, tray_rc, tray_component, config_clone);
-                }
-                Err(e) => {
-                    logger::log_error("TrayInitialization", e);
-                }
+        {
+            let mut state = state_rc.borrow_mut();
+            if config == state.config {
+                return;
             }
-        });
+            logger::log_info("BarApp::reload_config", "Config changed, applying");
 
-        // Workspaces таймер - проверка событий Hyprland
-        let workspaces_clone = workspaces_component.clone();
-        let config_workspaces = self.config.clone();
-        timeout_add_local(Duration::from_millis(config_workspaces.workspaces_check_interval_ms), move || {
-            let mut refreshed = false;
-            while rx.try_recv().is_ok() {
-                refreshed = true;
+            let new_css_path = css_path(&config);
+            if new_css_path != state.css_path {
+                if let Some(provider) = &state.css {
+                    reload_css(provider, new_css_path.as_deref());
+                }
+                state.css_watcher = new_css_path
+                    .as_deref()
+                    .and_then(|path| Self::watch_css(state_rc, path));
+                state.css_path = new_css_path;
             }
-            if refreshed {
-                workspaces_clone.borrow().refresh();
+
+            state.config = config;
+            // До пересборки модулей, чтобы они загрузили иконки из новой темы
+            apply_icon_theme(&state.config);
+
+            let state = &mut *state;
+            for bar in &mut state.bars {
+                bar.apply_config(&state.config, &state.hyprland_events, &state.worker);
             }
-            ControlFlow::Continue
-        });
+        }
 
-        // Lang таймер - обновление раскладки клавиатуры
-        let lang_clone = lang_component.clone();
-        let config_lang = self.config.clone();
-        timeout_add_local(Duration::from_millis(config_lang.lang_update_interval_ms), move || {
-            lang_clone.borrow().update();
-            ControlFlow::Continue
-        });
+        // Список мониторов в конфигурации мог измениться
+        Self::sync_bars(state_rc);
+    }
 
-        // Clock таймер - обновление времени
-        let clock_clone = clock_component.clone();
-        let config_clock = self.config.clone();
-        timeout_add_local(Duration::from_millis(config_clock.clock_update_interval_ms), move || {
-            clock_clone.borrow().update();
-            ControlFlow::Continue
-        });
+    fn watch_icons(state: &Rc<RefCell<BarState>>) -> Vec<FileMonitor> {
+        let weak = Rc::downgrade(state);
+        watch_dirs(&helpers::icon_watch_dirs(), ICON_REFRESH_DELAY, move || {
+            if let Some(state) = weak.upgrade() {
+                Self::refresh_icons(&state);
+            }
+        })
     }
 
-    fn start_tray_updater(
-        _tray_box: Box,
-        _root: Box,
-        tray: Rc<RefCell<Tray>>,
-        tray_component: TrayComponent,
-        config: BarConfig,
-    ) {
-        let tray_clone = tray.clone();
-        let component = Rc::new(RefCell::new(tray_component));
-        let component_clone = component.clone();
-        
-        timeout_add_local(Duration::from_secs(config.tray_update_interval_secs), move || {
-            let tray_ref = tray_clone.clone();
-            let component_ref = component_clone.clone();
-            
-            MainContext::default().spawn_local(async move {
-                let items = match tray_ref.borrow().get_items().await {
-                    Ok(items) => items,
-                    Err(e) => {
-                        logger::log_error("TrayUpdater", e);
-                        return;
-                    }
-                };
-                component_ref.borrow_mut().refresh(&items);
-            });
-            ControlFlow::Continue
+    /// Перестраивает индекс иконок в фоне и, если он изменился, перезагружает иконки модулей
+    fn refresh_icons(state_rc: &Rc<RefCell<BarState>>) {
+        let worker = state_rc.borrow().icon_worker.clone();
+        let weak = Rc::downgrade(state_rc);
+        MainContext::default().spawn_local(async move {
+            if worker.run(helpers::refresh_icon_index).await != Some(true) {
+                return;
+            }
+            let Some(state_rc) = weak.upgrade() else {
+                return;
+            };
+            logger::log_info("BarApp::refresh_icons", "Icon index rebuilt, reloading icons");
+            // Могли появиться новые темы, за корнями которых тоже нужно следить
+            let watchers = Self::watch_icons(&state_rc);
+            let mut state = state_rc.borrow_mut();
+            state.icon_watchers = watchers;
+            for content in state.bars.iter_mut().filter_map(|bar| bar.content.as_mut()) {
+                content.reload_icons();
+            }
         });
     }
-}
 
+    fn watch_css(state: &Rc<RefCell<BarState>>, path: &Path) -> Option<FileMonitor> {
+        let weak = Rc::downgrade(state);
+        let css_path = path.to_path_buf();
+        watch_file(path, move || {
+            let Some(state) = weak.upgrade() else {
+                return;
+            };
+            let state = state.borrow();
+            if let Some(provider) = &state.css {
+                reload_css(provider, Some(&css_path));
+            }
+        })
+    }
+}
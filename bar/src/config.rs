@@ -1,40 +1,645 @@
+use std::{collections::HashMap, env, fs, path::{Path, PathBuf}};
+
+use anyhow::{Context, Result, bail};
+use hyprland_workspaces::{ClientRule, ClientRules};
+use lang::LayoutFormat;
+use serde::{Deserialize, Deserializer, de::Error as _};
+
+/// Имя каталога приложения внутри `$XDG_CONFIG_HOME`
+const APP_DIR: &str = "oxidbar";
+/// Имя файла конфигурации
+const CONFIG_FILE: &str = "config.toml";
+
 /// Конфигурация для Bar приложения
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
 pub struct BarConfig {
-    /// Высота бара в пикселях
+    /// Толщина бара в пикселях: высота для `top`/`bottom`, ширина для `left`/`right`
+    #[serde(deserialize_with = "positive_i32")]
     pub height: i32,
-    /// Интервал проверки событий Hyprland для workspaces в миллисекундах
-    pub workspaces_check_interval_ms: u64,
-    /// Интервал обновления раскладки клавиатуры в миллисекундах
-    pub lang_update_interval_ms: u64,
+    /// Край экрана, к которому прикреплен бар
+    pub position: Position,
+    /// Слой layer-shell, в котором находится бар
+    pub layer: BarLayer,
+    /// Отступы бара от краев экрана
+    pub margin: Margins,
     /// Интервал обновления часов в миллисекундах
+    #[serde(deserialize_with = "positive_u64")]
     pub clock_update_interval_ms: u64,
     /// Интервал обновления tray в секундах
+    #[serde(deserialize_with = "positive_u64")]
     pub tray_update_interval_secs: u64,
     /// Размер иконок в пикселях
+    #[serde(deserialize_with = "positive_i32")]
     pub icon_size: i32,
+    /// Тема иконок; по умолчанию берется из настроек GTK
+    pub icon_theme: Option<String>,
     /// Отступы между элементами
+    #[serde(deserialize_with = "non_negative_i32")]
     pub spacing: i32,
+    /// Путь к файлу стилей (по умолчанию `$XDG_CONFIG_HOME/oxidbar/style.css`)
+    pub style: Option<PathBuf>,
+    /// Модули левой секции бара
+    pub modules_left: Vec<String>,
+    /// Модули центральной секции бара
+    pub modules_center: Vec<String>,
+    /// Модули правой секции бара
+    pub modules_right: Vec<String>,
+    /// Коннекторы мониторов, на которых показывается бар (например, `DP-1`).
+    /// Пустой список — бар на каждом мониторе
+    pub monitors: Vec<String>,
+    /// Бар получает фокус клавиатуры по клику, и по его кнопкам можно
+    /// перемещаться Tab'ом. По умолчанию выключено: клик по бару не забирает
+    /// фокус у окна, в котором набирается текст
+    pub keyboard_navigation: bool,
+    /// Настройки модуля workspaces
+    pub workspaces: WorkspacesConfig,
+    /// Настройки модуля lang
+    pub lang: LangConfig,
+    /// Устарело: workspaces обновляются по событиям Hyprland.
+    /// Ключ принимается, чтобы старые конфигурации загружались, и игнорируется
+    workspaces_check_interval_ms: Option<u64>,
+    /// Устарело: раскладка обновляется по событию Hyprland `activelayout`
+    lang_update_interval_ms: Option<u64>,
 }
 
 impl Default for BarConfig {
     fn default() -> Self {
         Self {
             height: 32,
-            workspaces_check_interval_ms: 100,
-            lang_update_interval_ms: 200,
+            position: Position::Top,
+            layer: BarLayer::Top,
+            margin: Margins::default(),
             clock_update_interval_ms: 1000,
             tray_update_interval_secs: 1,
             icon_size: 20,
+            icon_theme: None,
             spacing: 12,
+            style: None,
+            modules_left: vec!["workspaces".to_string()],
+            modules_center: Vec::new(),
+            modules_right: vec!["tray".to_string(), "lang".to_string(), "clock".to_string()],
+            monitors: Vec::new(),
+            keyboard_navigation: false,
+            workspaces: WorkspacesConfig::default(),
+            lang: LangConfig::default(),
+            workspaces_check_interval_ms: None,
+            lang_update_interval_ms: None,
         }
     }
 }
 
+/// Край экрана, к которому прикреплен бар
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Position {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Position {
+    /// Вертикальный бар (`left`/`right`): модули располагаются в столбец
+    pub fn is_vertical(self) -> bool {
+        matches!(self, Self::Left | Self::Right)
+    }
+}
+
+/// Слой layer-shell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarLayer {
+    Background,
+    Bottom,
+    Top,
+    Overlay,
+}
+
+/// Отступы бара от краев экрана в пикселях (секция `[margin]`)
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Margins {
+    #[serde(deserialize_with = "non_negative_i32")]
+    pub top: i32,
+    #[serde(deserialize_with = "non_negative_i32")]
+    pub bottom: i32,
+    #[serde(deserialize_with = "non_negative_i32")]
+    pub left: i32,
+    #[serde(deserialize_with = "non_negative_i32")]
+    pub right: i32,
+}
+
+/// Настройки модуля workspaces (секция `[workspaces]`)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct WorkspacesConfig {
+    /// Показывать workspace'ы всех мониторов, а не только монитора бара
+    pub all_monitors: bool,
+    /// Показывать special (scratchpad) workspace'ы
+    pub show_special: bool,
+    /// Шаблон подписи workspace'а с подстановками `{id}`, `{name}` и `{icon}`.
+    /// По умолчанию `{id}:`, в вертикальном баре — `{id}`
+    #[serde(deserialize_with = "optional_label_format")]
+    pub format: Option<String>,
+    /// Шаблон подписи special workspace'а; `{name}` — имя без префикса `special:`
+    #[serde(deserialize_with = "label_format")]
+    pub special_format: String,
+    /// Глифы для `{icon}` по id или имени workspace'а, например `1 = ""`
+    pub icons: HashMap<String, String>,
+    /// Workspace'ы, которые показываются, даже если Hyprland их еще не создал
+    pub persistent_workspaces: PersistentWorkspaces,
+    /// Переключение workspace'ов колесом мыши
+    pub scroll: ScrollMode,
+    /// Прокрутка вниз переключает на предыдущий workspace, а не на следующий
+    pub scroll_reverse: bool,
+    /// Переход с последнего workspace'а на первый и обратно
+    pub scroll_wrap: bool,
+    /// Показывать окна одного класса одной иконкой со счетчиком
+    pub group_clients: bool,
+    /// Максимум иконок в workspace'е; остальные окна показываются как «+N»
+    pub max_icons: Option<usize>,
+    /// Правила для окон (`[[workspaces.rules]]`): иконка, имя или скрытие по классу и заголовку
+    #[serde(deserialize_with = "client_rules")]
+    pub rules: ClientRules,
+}
+
+/// Настройки модуля lang (секция `[lang]`)
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct LangConfig {
+    /// `flag`, `short`, `full` или шаблон с `{flag}`, `{short}`, `{full}`,
+    /// `{code}`, `{country}` и `{variant}`
+    #[serde(deserialize_with = "layout_format")]
+    pub format: LayoutFormat,
+    /// Готовые подписи по названию раскладки или коду XKB, например `ru = "РУ"`
+    pub overrides: HashMap<String, String>,
+}
+
+/// Какие workspace'ы перебирает прокрутка колесом
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrollMode {
+    /// Прокрутка отключена
+    None,
+    /// Открытые workspace'ы монитора (`m+1`)
+    Monitor,
+    /// Открытые workspace'ы всех мониторов (`e+1`)
+    All,
+}
+
+/// Постоянные workspace'ы: общий список (`[1, 2, 3]`)
+/// или список для каждого монитора (`{ DP-1 = [1, 2], HDMI-A-1 = [3] }`)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum PersistentWorkspaces {
+    All(Vec<i32>),
+    PerMonitor(HashMap<String, Vec<i32>>),
+}
+
+impl Default for PersistentWorkspaces {
+    fn default() -> Self {
+        Self::All(Vec::new())
+    }
+}
+
+impl PersistentWorkspaces {
+    /// Постоянные workspace'ы монитора; для `None` (все мониторы) — объединение всех списков
+    pub fn for_monitor(&self, monitor: Option<&str>) -> Vec<i32> {
+        let mut ids = match (self, monitor) {
+            (Self::All(ids), _) => ids.clone(),
+            (Self::PerMonitor(map), Some(monitor)) => map.get(monitor).cloned().unwrap_or_default(),
+            (Self::PerMonitor(map), None) => map.values().flatten().copied().collect(),
+        };
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+
+impl Default for WorkspacesConfig {
+    fn default() -> Self {
+        Self {
+            all_monitors: false,
+            show_special: true,
+            format: None,
+            special_format: "{name}".to_string(),
+            icons: HashMap::new(),
+            persistent_workspaces: PersistentWorkspaces::default(),
+            scroll: ScrollMode::Monitor,
+            scroll_reverse: false,
+            scroll_wrap: true,
+            group_clients: false,
+            max_icons: None,
+            rules: ClientRules::default(),
+        }
+    }
+}
+
+impl WorkspacesConfig {
+    /// Подпись workspace'а по шаблону.
+    ///
+    /// `{icon}` ищется в [`WorkspacesConfig::icons`] сначала по имени, затем по id;
+    /// если глифа нет, подставляется имя.
+    pub fn label(&self, id: i32, name: &str, special: bool, vertical: bool) -> String {
+        let format = match &self.format {
+            _ if special => self.special_format.as_str(),
+            Some(format) => format.as_str(),
+            None if vertical => "{id}",
+            None => "{id}:",
+        };
+        let id = id.to_string();
+        let icon = self
+            .icons
+            .get(name)
+            .or_else(|| self.icons.get(&id))
+            .map_or(name, String::as_str);
+        format
+            .replace("{id}", &id)
+            .replace("{name}", name)
+            .replace("{icon}", icon)
+    }
+}
+
 impl BarConfig {
-    /// Создает новую конфигурацию с значениями по умолчанию
-    pub fn new() -> Self {
-        Self::default()
+    /// Загружает конфигурацию.
+    ///
+    /// Если `path` указан явно (через `--config`), файл обязан существовать.
+    /// Иначе читается `$XDG_CONFIG_HOME/oxidbar/config.toml`; при его отсутствии
+    /// используются значения по умолчанию.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => Self::from_file(path),
+            None => match default_config_path() {
+                Some(path) if path.exists() => Self::from_file(&path),
+                _ => Ok(Self::default()),
+            },
+        }
+    }
+
+    /// Нужно ли показывать бар на мониторе с данным коннектором
+    pub fn monitor_enabled(&self, connector: Option<&str>) -> bool {
+        self.monitors.is_empty()
+            || connector.is_some_and(|connector| self.monitors.iter().any(|m| m == connector))
+    }
+
+    /// Читает конфигурацию из файла, накладывая её поверх значений по умолчанию
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        let config = Self::from_toml(&content)
+            .with_context(|| format!("Invalid config {}", path.display()))?;
+        logger::log_info("BarConfig::load", format!("Loaded config from {}", path.display()));
+        Ok(config)
+    }
+
+    /// Разбирает конфигурацию из TOML строки.
+    ///
+    /// Ошибки содержат номер строки и столбца проблемного ключа.
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let mut config: Self = toml::from_str(content)?;
+        config.drop_deprecated();
+        Ok(config)
+    }
+
+    /// Предупреждает об устаревших ключах и сбрасывает их значения
+    fn drop_deprecated(&mut self) {
+        let deprecated = [
+            ("workspaces-check-interval-ms", self.workspaces_check_interval_ms.take()),
+            ("lang-update-interval-ms", self.lang_update_interval_ms.take()),
+        ];
+        for (key, value) in deprecated {
+            if value.is_some() {
+                logger::log_warning("BarConfig", format!("'{key}' is deprecated and ignored"));
+            }
+        }
+    }
+}
+
+/// Путь к конфигурации по умолчанию: `$XDG_CONFIG_HOME/oxidbar/config.toml`
+/// с откатом на `~/.config/oxidbar/config.toml`
+pub fn default_config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(CONFIG_FILE))
+}
+
+/// Каталог конфигурации приложения
+pub fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join(APP_DIR))
+}
+
+/// Извлекает `--config <path>` / `--config=<path>` из аргументов командной строки.
+///
+/// Возвращает путь к конфигурации и оставшиеся аргументы для GTK.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<(Option<PathBuf>, Vec<String>)> {
+    let mut config = None;
+    let mut rest = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--config" || arg == "-c" {
+            let Some(value) = args.next() else {
+                bail!("{arg} requires a path argument");
+            };
+            config = Some(PathBuf::from(value));
+        } else if let Some(value) = arg.strip_prefix("--config=") {
+            config = Some(PathBuf::from(value));
+        } else {
+            rest.push(arg);
+        }
+    }
+
+    Ok((config, rest))
+}
+
+fn positive_i32<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i32, D::Error> {
+    let value = i32::deserialize(deserializer)?;
+    if value <= 0 {
+        return Err(D::Error::custom(format!("expected a positive number, got {value}")));
+    }
+    Ok(value)
+}
+
+fn non_negative_i32<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i32, D::Error> {
+    let value = i32::deserialize(deserializer)?;
+    if value < 0 {
+        return Err(D::Error::custom(format!("expected a non-negative number, got {value}")));
+    }
+    Ok(value)
+}
+
+fn layout_format<'de, D: Deserializer<'de>>(deserializer: D) -> Result<LayoutFormat, D::Error> {
+    let format = String::deserialize(deserializer)?;
+    if format.is_empty() {
+        return Err(D::Error::custom("expected `flag`, `short`, `full` or a template, got an empty string"));
+    }
+    Ok(LayoutFormat::parse(&format))
+}
+
+/// Подстановки в шаблоне подписи workspace'а
+const LABEL_PLACEHOLDERS: [&str; 3] = ["{id}", "{name}", "{icon}"];
+
+/// Шаблон подписи workspace'а; неизвестные подстановки — ошибка, а не текст в подписи
+fn label_format<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let format = String::deserialize(deserializer)?;
+    let mut rest = format.as_str();
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let placeholder = &rest[start..=start + len];
+        if !LABEL_PLACEHOLDERS.contains(&placeholder) {
+            return Err(D::Error::custom(format!(
+                "unknown placeholder `{placeholder}`, expected one of {}",
+                LABEL_PLACEHOLDERS.join(", ")
+            )));
+        }
+        rest = &rest[start + len + 1..];
     }
+    Ok(format)
+}
+
+fn optional_label_format<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    label_format(deserializer).map(Some)
+}
+
+/// Правило для окон в том виде, в каком оно записано в конфигурации
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawClientRule {
+    class: Option<String>,
+    title: Option<String>,
+    icon: Option<String>,
+    name: Option<String>,
+    #[serde(default)]
+    hide: bool,
+}
+
+/// Компилирует regex правил при разборе, чтобы ошибка указывала на строку конфигурации
+fn client_rules<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ClientRules, D::Error> {
+    let rules = Vec::<RawClientRule>::deserialize(deserializer)?
+        .into_iter()
+        .map(|raw| {
+            let mut rule = ClientRule::new(raw.class.as_deref(), raw.title.as_deref())
+                .map_err(|e| D::Error::custom(format!("{e:#}")))?;
+            rule.icon = raw.icon;
+            rule.name = raw.name;
+            rule.hide = raw.hide;
+            Ok(rule)
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(ClientRules(rules))
 }
 
+fn positive_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let value = u64::deserialize(deserializer)?;
+    if value == 0 {
+        return Err(D::Error::custom("expected a positive number, got 0"));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_file_gives_defaults() {
+        assert_eq!(BarConfig::from_toml("").unwrap(), BarConfig::default());
+    }
+
+    #[test]
+    fn merges_over_defaults() {
+        let config = BarConfig::from_toml(
+            "height = 40\nicon-size = 24\nicon-theme = \"Papirus-Dark\"\nkeyboard-navigation = true\n",
+        )
+        .unwrap();
+        assert_eq!(config.height, 40);
+        assert_eq!(config.icon_size, 24);
+        assert_eq!(config.icon_theme.as_deref(), Some("Papirus-Dark"));
+        assert!(config.keyboard_navigation);
+        assert_eq!(config.spacing, BarConfig::default().spacing);
+    }
+
+    #[test]
+    fn ignores_deprecated_keys() {
+        // Конфигурация в формате первой версии
+        let config = BarConfig::from_toml(
+            "height = 32\nworkspaces-check-interval-ms = 100\nlang-update-interval-ms = 200\n\
+             clock-update-interval-ms = 1000\ntray-update-interval-secs = 1\nicon-size = 20\nspacing = 12\n",
+        )
+        .unwrap();
+        assert_eq!(config, BarConfig::default());
+    }
+
+    #[test]
+    fn unknown_key_reports_line() {
+        let err = BarConfig::from_toml("height = 40\nheigth = 30\n").unwrap_err();
+        let msg = format!("{err:#}");
+        assert!(msg.contains("line 2"), "{msg}");
+        assert!(msg.contains("heigth"), "{msg}");
+    }
+
+    #[test]
+    fn invalid_value_reports_line() {
+        let err = BarConfig::from_toml("spacing = 4\n\nheight = 0\n").unwrap_err();
+        let msg = format!("{err:#}");
+        assert!(msg.contains("line 3"), "{msg}");
+        assert!(msg.contains("positive"), "{msg}");
+    }
+
+    #[test]
+    fn parses_module_layout() {
+        let config = BarConfig::from_toml(
+            "modules-left = [\"workspaces\"]\nmodules-center = [\"clock\"]\nmodules-right = [\"tray\", \"lang\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.modules_left, ["workspaces"]);
+        assert_eq!(config.modules_center, ["clock"]);
+        assert_eq!(config.modules_right, ["tray", "lang"]);
+    }
+
+    #[test]
+    fn filters_monitors_by_connector() {
+        let config = BarConfig::from_toml("monitors = [\"DP-1\"]\n").unwrap();
+        assert!(config.monitor_enabled(Some("DP-1")));
+        assert!(!config.monitor_enabled(Some("HDMI-A-1")));
+        assert!(!config.monitor_enabled(None));
+        assert!(BarConfig::default().monitor_enabled(None));
+    }
+
+    #[test]
+    fn parses_position_layer_and_margins() {
+        let config = BarConfig::from_toml(
+            "position = \"left\"\nlayer = \"overlay\"\n\n[margin]\ntop = 8\nleft = 4\n",
+        )
+        .unwrap();
+        assert_eq!(config.position, Position::Left);
+        assert!(config.position.is_vertical());
+        assert_eq!(config.layer, BarLayer::Overlay);
+        assert_eq!(config.margin, Margins { top: 8, bottom: 0, left: 4, right: 0 });
+
+        let err = BarConfig::from_toml("position = \"middle\"\n").unwrap_err();
+        assert!(format!("{err:#}").contains("line 1"));
+        assert!(BarConfig::from_toml("[margin]\ntop = -1\n").is_err());
+    }
+
+    #[test]
+    fn parses_workspaces_section() {
+        let config = BarConfig::from_toml("[workspaces]\nall-monitors = true\n").unwrap();
+        assert!(config.workspaces.all_monitors);
+        assert!(config.workspaces.show_special);
+
+        let config = BarConfig::from_toml("[workspaces]\nshow-special = false\n").unwrap();
+        assert!(!config.workspaces.show_special);
+
+        let err = BarConfig::from_toml("[workspaces]\nall-monitor = true\n").unwrap_err();
+        assert!(format!("{err:#}").contains("line 2"));
+    }
+
+    #[test]
+    fn formats_workspace_labels() {
+        let config = BarConfig::from_toml(
+            "[workspaces]\nformat = \"{icon} {name}\"\n\n[workspaces.icons]\n1 = \"A\"\nmail = \"M\"\n",
+        )
+        .unwrap()
+        .workspaces;
+        assert_eq!(config.label(1, "1", false, false), "A 1");
+        assert_eq!(config.label(2, "mail", false, false), "M mail");
+        assert_eq!(config.label(3, "web", false, false), "web web");
+        assert_eq!(config.label(-98, "scratch", true, false), "scratch");
+
+        let defaults = WorkspacesConfig::default();
+        assert_eq!(defaults.label(4, "4", false, false), "4:");
+        assert_eq!(defaults.label(4, "4", false, true), "4");
+
+        let err = BarConfig::from_toml("[workspaces]\nshow-special = true\nformat = \"{id}: {icons}\"\n").unwrap_err();
+        let msg = format!("{err:#}");
+        assert!(msg.contains("line 3"), "{msg}");
+        assert!(msg.contains("{icons}"), "{msg}");
+        assert!(BarConfig::from_toml("[workspaces]\nspecial-format = \"{title}\"\n").is_err());
+    }
+
+    #[test]
+    fn parses_persistent_workspaces() {
+        let config = BarConfig::from_toml("[workspaces]\npersistent-workspaces = [3, 1, 2]\n").unwrap();
+        assert_eq!(config.workspaces.persistent_workspaces.for_monitor(Some("DP-1")), [1, 2, 3]);
+
+        let config = BarConfig::from_toml(
+            "[workspaces.persistent-workspaces]\nDP-1 = [1, 2]\nHDMI-A-1 = [2, 5]\n",
+        )
+        .unwrap();
+        let persistent = &config.workspaces.persistent_workspaces;
+        assert_eq!(persistent.for_monitor(Some("DP-1")), [1, 2]);
+        assert_eq!(persistent.for_monitor(Some("eDP-1")), Vec::<i32>::new());
+        assert_eq!(persistent.for_monitor(None), [1, 2, 5]);
+
+        assert!(BarConfig::from_toml("[workspaces]\npersistent-workspaces = \"1\"\n").is_err());
+    }
+
+    #[test]
+    fn parses_scroll_settings() {
+        let config = BarConfig::from_toml("[workspaces]\nscroll = \"all\"\nscroll-wrap = false\n").unwrap();
+        assert_eq!(config.workspaces.scroll, ScrollMode::All);
+        assert!(!config.workspaces.scroll_wrap);
+        assert!(!config.workspaces.scroll_reverse);
+        assert_eq!(BarConfig::default().workspaces.scroll, ScrollMode::Monitor);
+    }
+
+    #[test]
+    fn parses_client_grouping() {
+        let config = BarConfig::from_toml("[workspaces]\ngroup-clients = true\nmax-icons = 3\n").unwrap();
+        assert!(config.workspaces.group_clients);
+        assert_eq!(config.workspaces.max_icons, Some(3));
+        assert_eq!(BarConfig::default().workspaces.max_icons, None);
+    }
+
+    #[test]
+    fn parses_client_rules() {
+        let config = BarConfig::from_toml(
+            "[[workspaces.rules]]\nclass = '^steam_app_(\\d+)$'\nicon = 'steam_icon_$1'\nname = 'Steam'\n\n\
+             [[workspaces.rules]]\ntitle = 'Picture-in-Picture'\nhide = true\n",
+        )
+        .unwrap();
+        let rules = &config.workspaces.rules;
+        assert_eq!(rules.0.len(), 2);
+        let steam = rules.find("steam_app_570", "Dota 2").unwrap();
+        assert_eq!(steam.icon_for("steam_app_570").as_deref(), Some("steam_icon_570"));
+        assert_eq!(steam.name.as_deref(), Some("Steam"));
+        assert!(rules.find("firefox", "Picture-in-Picture").unwrap().hide);
+        assert!(rules.find("firefox", "GitHub").is_none());
+
+        let err = BarConfig::from_toml("[workspaces]\nall-monitors = true\n\n[[workspaces.rules]]\nclass = '('\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("line 4"), "{err}");
+        assert!(BarConfig::from_toml("[[workspaces.rules]]\nicon = 'x'\n").is_err());
+    }
+
+    #[test]
+    fn parses_lang_section() {
+        let config = BarConfig::from_toml("[lang]\nformat = \"short\"\n\n[lang.overrides]\nru = \"РУ\"\n").unwrap();
+        assert_eq!(config.lang.format, LayoutFormat::Short);
+        assert_eq!(config.lang.overrides.get("ru").map(String::as_str), Some("РУ"));
+        assert_eq!(BarConfig::default().lang.format, LayoutFormat::Flag);
+
+        let config = BarConfig::from_toml("[lang]\nformat = \"{flag} {short}\"\n").unwrap();
+        assert_eq!(config.lang.format, LayoutFormat::Custom("{flag} {short}".to_string()));
+        assert!(BarConfig::from_toml("[lang]\nformat = \"\"\n").is_err());
+    }
+
+    #[test]
+    fn parses_config_argument() {
+        let args = ["oxidbar", "--config", "/tmp/a.toml", "--gapplication-service"].map(String::from);
+        let (path, rest) = parse_args(args).unwrap();
+        assert_eq!(path, Some(PathBuf::from("/tmp/a.toml")));
+        assert_eq!(rest, vec!["oxidbar", "--gapplication-service"]);
+
+        let (path, _) = parse_args(["oxidbar", "--config=b.toml"].map(String::from)).unwrap();
+        assert_eq!(path, Some(PathBuf::from("b.toml")));
+        assert!(parse_args(["oxidbar", "--config"].map(String::from)).is_err());
+    }
+}
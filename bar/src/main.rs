@@ -3,20 +3,40 @@ mod config;
 mod ui;
 mod services;
 
+use std::process::ExitCode;
+
 use gtk4::{Application, prelude::*};
 use app::BarApp;
+use config::BarConfig;
 
-fn main() {
+fn main() -> ExitCode {
     logger::init();
-    
+
+    let (config_path, gtk_args) = match config::parse_args(std::env::args()) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            logger::log_error("Arguments", format!("{e:#}"));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let config = match BarConfig::load(config_path.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            logger::log_error("BarConfig::load", format!("{e:#}"));
+            return ExitCode::FAILURE;
+        }
+    };
+
     let app = Application::builder()
         .application_id("rs.regimentor.oxidbar")
         .build();
 
-    let bar_app = BarApp::new();
+    let bar_app = BarApp::new(config, config_path);
     app.connect_activate(move |app| {
         bar_app.build_ui(app);
     });
     
-    app.run();
+    app.run_with_args(&gtk_args);
+    ExitCode::SUCCESS
 }
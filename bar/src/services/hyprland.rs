@@ -1,37 +1,133 @@
-use hyprland::event_listener::EventListener;
-use std::{sync::mpsc, thread};
+use async_channel::Sender;
+use glib::MainContext;
+use hyprland::{event_listener::EventListener, shared::Address};
+use std::{cell::{Cell, RefCell}, rc::{Rc, Weak}, thread};
 
-/// Запускает слушатель событий Hyprland в отдельном потоке
-pub fn start_hyprland_event_listener(tx: mpsc::Sender<()>) {
+/// Событие Hyprland, доставленное на главный поток
+#[derive(Debug, Clone, PartialEq)]
+pub enum HyprlandEvent {
+    /// Активный workspace сменился
+    WorkspaceChanged { id: i32, name: String },
+    /// Создан workspace
+    WorkspaceAdded { id: i32, name: String },
+    /// Удален workspace
+    WorkspaceDeleted { id: i32 },
+    /// Workspace перенесен на другой монитор
+    WorkspaceMoved { id: i32, monitor: String },
+    /// Workspace переименован
+    WorkspaceRenamed { id: i32, name: String },
+    /// Открыто окно
+    WindowOpened { address: Address, workspace: String, class: String, title: String },
+    /// Закрыто окно
+    WindowClosed { address: Address },
+    /// Окно перенесено на другой workspace
+    WindowMoved { address: Address, workspace_id: i32 },
+    /// Фокус перешел на другое окно; `None` — фокус ни на одном окне
+    ActiveWindowChanged { address: Option<Address> },
+    /// Изменился заголовок окна
+    WindowTitleChanged { address: Address, title: String },
+    /// Окно запросило внимание
+    UrgentStateChanged { address: Address },
+    /// На мониторе открыт special workspace
+    SpecialChanged { monitor: String, name: String },
+    /// На мониторе закрыт special workspace
+    SpecialRemoved { monitor: String },
+    /// Подключен монитор
+    MonitorAdded { name: String },
+    /// Отключен монитор
+    MonitorRemoved { name: String },
+    /// На клавиатуре сменилась раскладка
+    LayoutChanged { keyboard: String, layout: String },
+}
+
+/// Запускает слушатель событий Hyprland в отдельном потоке.
+///
+/// События передаются в `tx`; главный поток получает их через [`HyprlandEvents::attach`].
+pub fn start_hyprland_event_listener(tx: Sender<HyprlandEvent>) {
     thread::spawn(move || {
         let mut listener = EventListener::new();
 
-        let send = |desc: &str, tx: &mpsc::Sender<()>| {
-            if let Err(err) = tx.send(()) {
-                logger::log_error(&format!("HyprlandListener({})", desc), err);
+        let handler = |desc: &'static str| {
+            let tx = tx.clone();
+            move |event: HyprlandEvent| {
+                if let Err(err) = tx.send_blocking(event) {
+                    logger::log_error(&format!("HyprlandListener({})", desc), err);
+                }
             }
         };
 
-        let tx_ws = tx.clone();
-        listener.add_workspace_changed_handler(move |_| send("workspace_changed", &tx_ws));
+        let send = handler("workspace_changed");
+        listener.add_workspace_changed_handler(move |data| {
+            send(HyprlandEvent::WorkspaceChanged { id: data.id, name: data.name.to_string() })
+        });
+
+        let send = handler("workspace_added");
+        listener.add_workspace_added_handler(move |data| {
+            send(HyprlandEvent::WorkspaceAdded { id: data.id, name: data.name.to_string() })
+        });
+
+        let send = handler("workspace_deleted");
+        listener.add_workspace_deleted_handler(move |data| send(HyprlandEvent::WorkspaceDeleted { id: data.id }));
+
+        let send = handler("workspace_moved");
+        listener.add_workspace_moved_handler(move |data| {
+            send(HyprlandEvent::WorkspaceMoved { id: data.id, monitor: data.monitor })
+        });
+
+        let send = handler("workspace_renamed");
+        listener.add_workspace_renamed_handler(move |data| {
+            send(HyprlandEvent::WorkspaceRenamed { id: data.id, name: data.name })
+        });
+
+        let send = handler("window_opened");
+        listener.add_window_opened_handler(move |data| {
+            send(HyprlandEvent::WindowOpened {
+                address: data.window_address,
+                workspace: data.workspace_name,
+                class: data.window_class,
+                title: data.window_title,
+            })
+        });
 
-        let tx_added = tx.clone();
-        listener.add_workspace_added_handler(move |_| send("workspace_added", &tx_added));
+        let send = handler("window_closed");
+        listener.add_window_closed_handler(move |address| send(HyprlandEvent::WindowClosed { address }));
 
-        let tx_deleted = tx.clone();
-        listener.add_workspace_deleted_handler(move |_| send("workspace_deleted", &tx_deleted));
+        let send = handler("window_moved");
+        listener.add_window_moved_handler(move |data| {
+            send(HyprlandEvent::WindowMoved { address: data.window_address, workspace_id: data.workspace_id })
+        });
 
-        let tx_moved = tx.clone();
-        listener.add_workspace_moved_handler(move |_| send("workspace_moved", &tx_moved));
+        let send = handler("active_window_changed");
+        listener.add_active_window_changed_handler(move |data| {
+            send(HyprlandEvent::ActiveWindowChanged { address: data.map(|window| window.address) })
+        });
 
-        let tx_renamed = tx.clone();
-        listener.add_workspace_renamed_handler(move |_| send("workspace_renamed", &tx_renamed));
+        let send = handler("window_title_changed");
+        listener.add_window_title_changed_handler(move |data| {
+            send(HyprlandEvent::WindowTitleChanged { address: data.address, title: data.title })
+        });
 
-        let tx_open = tx.clone();
-        listener.add_window_opened_handler(move |_| send("window_opened", &tx_open));
+        let send = handler("urgent_state_changed");
+        listener.add_urgent_state_changed_handler(move |address| send(HyprlandEvent::UrgentStateChanged { address }));
 
-        let tx_close = tx.clone();
-        listener.add_window_closed_handler(move |_| send("window_closed", &tx_close));
+        let send = handler("changed_special");
+        listener.add_changed_special_handler(move |data| {
+            send(HyprlandEvent::SpecialChanged { monitor: data.monitor_name, name: data.workspace_name })
+        });
+
+        let send = handler("special_removed");
+        listener.add_special_removed_handler(move |monitor| send(HyprlandEvent::SpecialRemoved { monitor }));
+
+        let send = handler("monitor_added");
+        listener.add_monitor_added_handler(move |data| send(HyprlandEvent::MonitorAdded { name: data.name }));
+
+        let send = handler("monitor_removed");
+        listener.add_monitor_removed_handler(move |name| send(HyprlandEvent::MonitorRemoved { name }));
+
+        let send = handler("layout_changed");
+        listener.add_layout_changed_handler(move |data| {
+            send(HyprlandEvent::LayoutChanged { keyboard: data.keyboard_name, layout: data.layout_name })
+        });
 
         if let Err(err) = listener.start_listener() {
             logger::log_error("HyprlandListener::start", err);
@@ -39,3 +135,70 @@ pub fn start_hyprland_event_listener(tx: mpsc::Sender<()>) {
     });
 }
 
+/// Подписчик на события Hyprland
+type Subscriber = Rc<dyn Fn(&HyprlandEvent)>;
+
+/// Рассылка уведомлений о событиях Hyprland подписчикам на главном потоке.
+///
+/// Один слушатель событий обслуживает все бары и модули: каждый модуль
+/// подписывается сам и получает уведомление независимо от остальных.
+#[derive(Clone, Default)]
+pub struct HyprlandEvents {
+    subscribers: Rc<RefCell<Vec<(u64, Subscriber)>>>,
+    next_id: Rc<Cell<u64>>,
+}
+
+impl HyprlandEvents {
+    /// Подписывается на события; подписка действует, пока жив [`Subscription`]
+    pub fn subscribe(&self, callback: impl Fn(&HyprlandEvent) + 'static) -> Subscription {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.subscribers.borrow_mut().push((id, Rc::new(callback)));
+        Subscription {
+            id,
+            subscribers: Rc::downgrade(&self.subscribers),
+        }
+    }
+
+    /// Доставляет события из канала подписчикам.
+    ///
+    /// Задача в главном цикле glib просыпается только при поступлении события
+    /// и завершается, когда слушатель закрывает канал.
+    pub fn attach(&self, rx: async_channel::Receiver<HyprlandEvent>) {
+        let events = self.clone();
+        MainContext::default().spawn_local(async move {
+            while let Ok(event) = rx.recv().await {
+                events.emit(&event);
+            }
+            logger::log_warning("HyprlandEvents", "Event listener stopped");
+        });
+    }
+
+    /// Передает событие всем подписчикам
+    pub fn emit(&self, event: &HyprlandEvent) {
+        // Копия списка: подписчик может подписаться или отписаться во время обработки
+        let subscribers: Vec<Subscriber> = self
+            .subscribers
+            .borrow()
+            .iter()
+            .map(|(_, callback)| callback.clone())
+            .collect();
+        for callback in subscribers {
+            callback(event);
+        }
+    }
+}
+
+/// Активная подписка на [`HyprlandEvents`]; при удалении подписка снимается
+pub struct Subscription {
+    id: u64,
+    subscribers: Weak<RefCell<Vec<(u64, Subscriber)>>>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(subscribers) = self.subscribers.upgrade() {
+            subscribers.borrow_mut().retain(|(id, _)| *id != self.id);
+        }
+    }
+}
@@ -1,4 +1,8 @@
 pub mod hyprland;
+pub mod watcher;
+pub mod worker;
 
-pub use hyprland::start_hyprland_event_listener;
+pub use hyprland::{HyprlandEvent, HyprlandEvents, start_hyprland_event_listener};
+pub use watcher::{watch_dirs, watch_file};
+pub use worker::Worker;
 
@@ -0,0 +1,95 @@
+use gtk4::gio::{Cancellable, File, FileMonitor, FileMonitorEvent, FileMonitorFlags, prelude::*};
+use glib::{SourceId, timeout_add_local_once};
+use std::{cell::RefCell, path::{Path, PathBuf}, rc::Rc, time::Duration};
+
+/// Задержка, в течение которой события одного сохранения склеиваются в одно
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Откладывает вызов `on_change` до паузы в событиях: каждое новое событие
+/// в течение `delay` переносит вызов, так что серия событий дает один вызов
+#[derive(Clone)]
+struct Debouncer {
+    delay: Duration,
+    on_change: Rc<dyn Fn()>,
+    pending: Rc<RefCell<Option<SourceId>>>,
+}
+
+impl Debouncer {
+    fn new(delay: Duration, on_change: impl Fn() + 'static) -> Self {
+        Self {
+            delay,
+            on_change: Rc::new(on_change),
+            pending: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    fn trigger(&self) {
+        if let Some(source) = self.pending.borrow_mut().take() {
+            source.remove();
+        }
+        let on_change = self.on_change.clone();
+        let pending = self.pending.clone();
+        let source = timeout_add_local_once(self.delay, move || {
+            pending.borrow_mut().take();
+            on_change();
+        });
+        *self.pending.borrow_mut() = Some(source);
+    }
+}
+
+/// Следит за файлом через inotify (GFileMonitor) и вызывает `on_change` после его изменения.
+///
+/// Наблюдение ведется за родительским каталогом, поэтому атомарное сохранение
+/// редакторами (запись во временный файл + rename) тоже отслеживается.
+/// Монитор работает, пока жив возвращаемый `FileMonitor`.
+pub fn watch_file(path: &Path, on_change: impl Fn() + 'static) -> Option<FileMonitor> {
+    let file = File::for_path(path);
+    let monitor = match file.monitor_file(FileMonitorFlags::WATCH_MOVES, None::<&Cancellable>) {
+        Ok(monitor) => monitor,
+        Err(e) => {
+            logger::log_error(&format!("FileWatcher({})", path.display()), e);
+            return None;
+        }
+    };
+
+    let debouncer = Debouncer::new(DEBOUNCE, on_change);
+    monitor.connect_changed(move |_, _, _, event| {
+        if !matches!(
+            event,
+            FileMonitorEvent::ChangesDoneHint
+                | FileMonitorEvent::Created
+                | FileMonitorEvent::MovedIn
+                | FileMonitorEvent::Renamed
+        ) {
+            return;
+        }
+        debouncer.trigger();
+    });
+
+    logger::log_info("FileWatcher", format!("Watching {}", path.display()));
+    Some(monitor)
+}
+
+/// Следит за каталогами (без вложенных) и вызывает `on_change` один раз
+/// после серии изменений, когда в течение `delay` не было новых событий.
+///
+/// Мониторы работают, пока живы возвращаемые `FileMonitor`.
+pub fn watch_dirs(dirs: &[PathBuf], delay: Duration, on_change: impl Fn() + 'static) -> Vec<FileMonitor> {
+    let debouncer = Debouncer::new(delay, on_change);
+    let monitors: Vec<FileMonitor> = dirs
+        .iter()
+        .filter(|dir| dir.is_dir())
+        .filter_map(|dir| {
+            let monitor = File::for_path(dir)
+                .monitor_directory(FileMonitorFlags::WATCH_MOVES, None::<&Cancellable>)
+                .map_err(|e| logger::log_error(&format!("FileWatcher({})", dir.display()), e))
+                .ok()?;
+            let debouncer = debouncer.clone();
+            monitor.connect_changed(move |_, _, _, _| debouncer.trigger());
+            Some(monitor)
+        })
+        .collect();
+
+    logger::log_info("FileWatcher", format!("Watching {} directories", monitors.len()));
+    monitors
+}
@@ -0,0 +1,47 @@
+use async_channel::Sender;
+use std::{panic::{AssertUnwindSafe, catch_unwind}, thread};
+
+/// Задача для фонового потока
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Фоновый поток для блокирующих запросов (Hyprland IPC, чтение файлов).
+///
+/// Задачи выполняются по очереди; результат возвращается в главный цикл
+/// через [`Worker::run`], поэтому UI не блокируется, даже если Hyprland
+/// отвечает медленно или перезапускается.
+#[derive(Clone)]
+pub struct Worker {
+    jobs: Sender<Job>,
+}
+
+impl Worker {
+    /// Запускает фоновый поток с указанным именем
+    pub fn spawn(name: &str) -> Self {
+        let (jobs, rx) = async_channel::unbounded::<Job>();
+        let thread_name = name.to_string();
+        let spawned = thread::Builder::new().name(thread_name.clone()).spawn(move || {
+            while let Ok(job) = rx.recv_blocking() {
+                // Паника в одной задаче не должна останавливать поток
+                if catch_unwind(AssertUnwindSafe(job)).is_err() {
+                    logger::log_error(&format!("Worker({thread_name})"), "Job panicked");
+                }
+            }
+        });
+        if let Err(e) = spawned {
+            logger::log_error(&format!("Worker({name})"), e);
+        }
+        Self { jobs }
+    }
+
+    /// Выполняет `task` в фоновом потоке и возвращает результат.
+    ///
+    /// `None`, если поток недоступен или задача завершилась паникой.
+    pub async fn run<T: Send + 'static>(&self, task: impl FnOnce() -> T + Send + 'static) -> Option<T> {
+        let (tx, rx) = async_channel::bounded(1);
+        let job: Job = Box::new(move || {
+            let _ = tx.send_blocking(task());
+        });
+        self.jobs.send(job).await.ok()?;
+        rx.recv().await.ok()
+    }
+}
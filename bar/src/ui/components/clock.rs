@@ -1,22 +1,69 @@
-use gtk4::{Label, prelude::*};
-use time_utils::format_local_default;
+use gtk4::{Justification, Label, Orientation, Widget, prelude::*};
+use glib::{timeout_add_local, ControlFlow};
+use std::time::Duration;
+use time_utils::{MULTILINE_PATTERN, format_local, format_local_default};
+
+use super::module::{BarModule, ModuleContext, ModuleSources};
 
 /// Компонент для отображения времени
 pub struct ClockComponent {
     label: Label,
+    /// Время в несколько строк для вертикального бара
+    multiline: bool,
+    sources: ModuleSources,
 }
 
 impl ClockComponent {
     /// Создает новый компонент clock
-    pub fn new(label: Label) -> Self {
+    pub fn new(ctx: &ModuleContext) -> Self {
+        let multiline = ctx.orientation() == Orientation::Vertical;
+        let label = Label::new(None);
         label.add_css_class("clock");
-        label.set_halign(gtk4::Align::End);
-        Self { label }
+        if multiline {
+            label.set_justify(Justification::Center);
+            label.set_halign(gtk4::Align::Center);
+        } else {
+            label.set_halign(gtk4::Align::End);
+        }
+        Self {
+            label,
+            multiline,
+            sources: ModuleSources::default(),
+        }
     }
 
     /// Обновляет отображаемое время
-    pub fn update(&self) {
-        self.label.set_text(&format_local_default());
+    fn update(label: &Label, multiline: bool) {
+        if multiline {
+            label.set_text(&format_local(MULTILINE_PATTERN));
+        } else {
+            label.set_text(&format_local_default());
+        }
     }
 }
 
+impl BarModule for ClockComponent {
+    fn widget(&self) -> Widget {
+        self.label.clone().upcast()
+    }
+
+    fn start(&mut self, ctx: &ModuleContext) {
+        Self::update(&self.label, self.multiline);
+
+        // Clock таймер - обновление времени
+        let label = self.label.clone();
+        let multiline = self.multiline;
+        self.sources.push(timeout_add_local(
+            Duration::from_millis(ctx.config.clock_update_interval_ms),
+            move || {
+                Self::update(&label, multiline);
+                ControlFlow::Continue
+            },
+        ));
+    }
+
+    fn on_config(&mut self, ctx: &ModuleContext) {
+        self.sources.clear();
+        self.start(ctx);
+    }
+}
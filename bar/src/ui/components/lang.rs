@@ -1,31 +1,270 @@
-use gtk4::{Label, prelude::*};
-use lang::get_layout_flag;
+use gtk4::{
+    Box, Button, EventControllerScroll, EventControllerScrollFlags, GestureClick, Label, Orientation, Popover, Widget,
+    gdk::{BUTTON_PRIMARY, BUTTON_SECONDARY},
+    glib::Propagation, prelude::*,
+};
+use glib::MainContext;
+use lang::{KeyboardLayout, LayoutSwitch, find_layout, format_layout, get_main_keyboard_layout, switch_layout};
+use std::{cell::RefCell, rc::Rc};
 
-/// Компонент для отображения текущей раскладки клавиатуры
+use crate::config::LangConfig;
+use crate::services::{HyprlandEvent, Worker};
+use super::module::{BarModule, ModuleContext, ModuleSources, align_end, set_error};
+
+/// Компонент для отображения текущей раскладки клавиатуры.
+///
+/// Начальная раскладка запрашивается в фоновом потоке, дальше она
+/// обновляется по событию Hyprland `activelayout`. Клик и колесо мыши
+/// переключают раскладку, правый клик открывает список раскладок.
 pub struct LangComponent {
     label: Label,
+    popover: Popover,
+    state: Rc<RefCell<LangState>>,
+    sources: ModuleSources,
+}
+
+/// Основная клавиатура и показанная раскладка
+#[derive(Default)]
+struct LangState {
+    /// События других клавиатур игнорируются; `None`, пока основная неизвестна
+    keyboard: Option<String>,
+    layout: Option<String>,
+    config: LangConfig,
 }
 
 impl LangComponent {
     /// Создает новый компонент lang
-    pub fn new(label: Label) -> Self {
+    pub fn new(ctx: &ModuleContext) -> Self {
+        let label = Label::new(None);
         label.add_css_class("lang");
-        label.set_halign(gtk4::Align::End);
-        label.set_margin_end(12);
-        Self { label }
+        align_end(&label, ctx.orientation(), 12);
+
+        let popover = Popover::new();
+        popover.add_css_class("lang-menu");
+        popover.set_parent(&label);
+        popover.set_has_arrow(false);
+        popover.set_autohide(true);
+
+        let state = Rc::new(RefCell::new(LangState::default()));
+        Self::add_switch_controllers(&label, &state, &ctx.worker);
+        Self::add_menu_controller(&label, &popover, &state, &ctx.worker);
+        Self { label, popover, state, sources: ModuleSources::default() }
     }
 
-    /// Обновляет отображаемую раскладку
-    pub fn update(&self) {
-        match get_layout_flag() {
-            Ok(flag) => {
-                self.label.set_text(&flag);
+    /// Клавиатура для `switchxkblayout`: основная, а пока она неизвестна — текущая
+    fn keyboard(state: &Rc<RefCell<LangState>>) -> String {
+        state.borrow().keyboard.clone().unwrap_or_else(|| "current".to_string())
+    }
+
+    /// Переключает раскладку в фоновом потоке и показывает ту, что стала активной
+    fn switch(label: &Label, state: &Rc<RefCell<LangState>>, worker: &Worker, switch: LayoutSwitch) {
+        let keyboard = Self::keyboard(state);
+        let label = label.clone();
+        let state = state.clone();
+        let worker = worker.clone();
+        MainContext::default().spawn_local(async move {
+            let result = worker
+                .run(move || {
+                    switch_layout(&keyboard, switch)?;
+                    get_main_keyboard_layout()
+                })
+                .await;
+            match result {
+                Some(Ok(current)) => {
+                    let mut state = state.borrow_mut();
+                    state.keyboard = Some(current.keyboard);
+                    Self::show(&label, &mut state, &current.layout);
+                }
+                Some(Err(e)) => logger::log_error("LangComponent::switch", e),
+                None => logger::log_error("LangComponent::switch", "Worker unavailable"),
             }
-            Err(e) => {
-                self.label.set_text("—");
-                logger::log_error("LangComponent", e);
+        });
+    }
+
+    /// Левый клик — следующая раскладка, колесо — следующая или предыдущая
+    fn add_switch_controllers(label: &Label, state: &Rc<RefCell<LangState>>, worker: &Worker) {
+        let click = GestureClick::new();
+        click.set_button(BUTTON_PRIMARY);
+        let click_state = state.clone();
+        let click_worker = worker.clone();
+        click.connect_released(move |gesture, _, _, _| {
+            if let Some(label) = gesture.widget().and_downcast::<Label>() {
+                Self::switch(&label, &click_state, &click_worker, LayoutSwitch::Next);
             }
+        });
+        label.add_controller(click);
+
+        let scroll = EventControllerScroll::new(
+            EventControllerScrollFlags::BOTH_AXES | EventControllerScrollFlags::DISCRETE,
+        );
+        let scroll_state = state.clone();
+        let scroll_worker = worker.clone();
+        scroll.connect_scroll(move |controller, dx, dy| {
+            let delta = if dy != 0.0 { dy } else { dx };
+            let Some(label) = controller.widget().and_downcast::<Label>() else {
+                return Propagation::Proceed;
+            };
+            if delta == 0.0 {
+                return Propagation::Proceed;
+            }
+            let switch = if delta > 0.0 { LayoutSwitch::Next } else { LayoutSwitch::Previous };
+            Self::switch(&label, &scroll_state, &scroll_worker, switch);
+            Propagation::Stop
+        });
+        label.add_controller(scroll);
+    }
+
+    /// Правый клик открывает список раскладок основной клавиатуры
+    fn add_menu_controller(label: &Label, popover: &Popover, state: &Rc<RefCell<LangState>>, worker: &Worker) {
+        let click = GestureClick::new();
+        click.set_button(BUTTON_SECONDARY);
+        let popover = popover.clone();
+        let state = state.clone();
+        let worker = worker.clone();
+        click.connect_pressed(move |gesture, _, _, _| {
+            let Some(label) = gesture.widget().and_downcast::<Label>() else {
+                return;
+            };
+            let popover = popover.clone();
+            let state = state.clone();
+            let worker = worker.clone();
+            MainContext::default().spawn_local(async move {
+                match worker.run(get_main_keyboard_layout).await {
+                    Some(Ok(current)) => {
+                        Self::fill_menu(&label, &popover, &state, &worker, &current);
+                        popover.popup();
+                    }
+                    Some(Err(e)) => logger::log_error("LangComponent::menu", e),
+                    None => logger::log_error("LangComponent::menu", "Worker unavailable"),
+                }
+            });
+        });
+        label.add_controller(click);
+    }
+
+    fn fill_menu(
+        label: &Label,
+        popover: &Popover,
+        state: &Rc<RefCell<LangState>>,
+        worker: &Worker,
+        current: &KeyboardLayout,
+    ) {
+        let list = Box::new(Orientation::Vertical, 0);
+        // active_keymap — название из XKB, у вариантов оно может не совпасть с нашим
+        let active = current.layouts.iter().position(|layout| layout.name == current.layout).or_else(|| {
+            let known = find_layout(&current.layout)?;
+            current.layouts.iter().position(|layout| layout.code == known.code)
+        });
+        for (index, layout) in current.layouts.iter().enumerate() {
+            let button = Button::with_label(&layout.name);
+            button.add_css_class("flat");
+            if active == Some(index) {
+                button.add_css_class("active");
+            }
+            let Ok(index) = u8::try_from(index) else {
+                break;
+            };
+            let label = label.clone();
+            let popover = popover.clone();
+            let state = state.clone();
+            let worker = worker.clone();
+            button.connect_clicked(move |_| {
+                popover.popdown();
+                Self::switch(&label, &state, &worker, LayoutSwitch::Index(index));
+            });
+            list.append(&button);
         }
+        popover.set_child(Some(&list));
+        state.borrow_mut().keyboard = Some(current.keyboard.clone());
+    }
+
+    /// Подсказка с полным названием раскладки и именем клавиатуры
+    fn update_tooltip(label: &Label, state: &LangState) {
+        let Some(layout) = &state.layout else {
+            return;
+        };
+        let tooltip = match &state.keyboard {
+            Some(keyboard) => format!("{layout}\n{keyboard}"),
+            None => layout.clone(),
+        };
+        label.set_tooltip_text(Some(&tooltip));
+    }
+
+    /// Показывает раскладку, если она отличается от текущей
+    fn show(label: &Label, state: &mut LangState, layout: &str) {
+        if state.layout.as_deref() == Some(layout) {
+            return;
+        }
+        label.set_text(&format_layout(layout, &state.config.format, &state.config.overrides));
+        set_error(label, None);
+        state.layout = Some(layout.to_string());
+        Self::update_tooltip(label, state);
+    }
+
+    fn show_error(label: &Label, state: &mut LangState, error: &str) {
+        label.set_text("—");
+        set_error(label, Some(error));
+        state.layout = None;
+    }
+}
+
+impl BarModule for LangComponent {
+    fn widget(&self) -> Widget {
+        self.label.clone().upcast()
+    }
+
+    fn start(&mut self, ctx: &ModuleContext) {
+        {
+            let mut state = self.state.borrow_mut();
+            state.config = ctx.config.lang.clone();
+            // Перерисовать с новым форматом после ответа на запрос
+            state.layout = None;
+        }
+
+        let label = self.label.clone();
+        let state = self.state.clone();
+        self.sources.hold(ctx.hyprland_events.subscribe(move |event| {
+            let HyprlandEvent::LayoutChanged { keyboard, layout } = event else {
+                return;
+            };
+            let mut state = state.borrow_mut();
+            if state.keyboard.as_ref().is_none_or(|main| main == keyboard) {
+                Self::show(&label, &mut state, layout);
+            }
+        }));
+
+        let label = self.label.clone();
+        let state = self.state.clone();
+        let worker = ctx.worker.clone();
+        MainContext::default().spawn_local(async move {
+            let result = worker.run(get_main_keyboard_layout).await;
+            let mut state = state.borrow_mut();
+            match result {
+                Some(Ok(current)) => {
+                    // Событие могло прийти раньше ответа и уже содержит более свежую раскладку
+                    if state.layout.is_none() {
+                        Self::show(&label, &mut state, &current.layout);
+                    }
+                    state.keyboard = Some(current.keyboard);
+                    Self::update_tooltip(&label, &state);
+                }
+                Some(Err(e)) => {
+                    Self::show_error(&label, &mut state, &e.to_string());
+                    logger::log_error("LangComponent", e);
+                }
+                None => Self::show_error(&label, &mut state, "Worker unavailable"),
+            }
+        });
+    }
+
+    fn on_config(&mut self, ctx: &ModuleContext) {
+        self.sources.clear();
+        self.start(ctx);
     }
 }
 
+impl Drop for LangComponent {
+    fn drop(&mut self) {
+        self.popover.unparent();
+    }
+}
@@ -1,10 +1,13 @@
+pub mod module;
+pub mod registry;
 pub mod workspaces;
 pub mod tray;
 pub mod clock;
 pub mod lang;
 
+pub use module::{BoxedModule, ModuleContext};
+pub use registry::build_module;
 pub use workspaces::WorkspacesComponent;
 pub use tray::TrayComponent;
 pub use clock::ClockComponent;
 pub use lang::LangComponent;
-
@@ -0,0 +1,153 @@
+use gtk4::{Orientation, Widget, prelude::*};
+use glib::SourceId;
+use std::{any::Any, cell::RefCell, rc::{Rc, Weak}};
+
+use crate::config::BarConfig;
+use crate::services::{HyprlandEvents, Worker};
+
+/// CSS класс, который получает корневой виджет каждого модуля
+pub const MODULE_CSS_CLASS: &str = "module";
+/// CSS класс модуля в состоянии ошибки
+pub const ERROR_CSS_CLASS: &str = "error";
+
+/// Общий интерфейс модулей бара.
+///
+/// Модуль создается реестром по имени из конфигурации, затем бар
+/// добавляет [`BarModule::widget`] в секцию и вызывает [`BarModule::start`].
+/// Все таймеры и подписки модуль хранит в [`ModuleSources`], поэтому они
+/// останавливаются вместе с удалением модуля.
+pub trait BarModule {
+    /// Корневой виджет модуля
+    fn widget(&self) -> Widget;
+
+    /// Запускает обновление модуля: первичную отрисовку, таймеры, подписки
+    fn start(&mut self, ctx: &ModuleContext);
+
+    /// Применяет новую конфигурацию без пересоздания виджета
+    fn on_config(&mut self, ctx: &ModuleContext);
+
+    /// Вызывается после перестройки индекса иконок: модуль с иконками
+    /// приложений должен найти их заново
+    fn on_icons_changed(&mut self, _ctx: &ModuleContext) {}
+}
+
+/// Модуль, созданный реестром
+pub type BoxedModule = std::boxed::Box<dyn BarModule>;
+
+/// Окружение, в котором запускаются модули
+#[derive(Clone)]
+pub struct ModuleContext {
+    /// Текущая конфигурация бара
+    pub config: BarConfig,
+    /// Коннектор монитора, на котором находится бар (совпадает с именем монитора в Hyprland)
+    pub monitor: Option<String>,
+    /// Уведомления о событиях Hyprland
+    pub hyprland_events: HyprlandEvents,
+    /// Фоновый поток для блокирующих запросов
+    pub worker: Worker,
+}
+
+impl ModuleContext {
+    /// Направление, в котором модуль раскладывает свои элементы
+    pub fn orientation(&self) -> Orientation {
+        if self.config.position.is_vertical() {
+            Orientation::Vertical
+        } else {
+            Orientation::Horizontal
+        }
+    }
+}
+
+/// Источники событий glib (таймеры и т.п.) и подписки, принадлежащие модулю.
+///
+/// При удалении или вызове [`ModuleSources::clear`] все источники отключаются,
+/// а подписки снимаются.
+#[derive(Default)]
+pub struct ModuleSources(Rc<RefCell<SourcesInner>>);
+
+#[derive(Default)]
+struct SourcesInner {
+    sources: Vec<SourceId>,
+    guards: Vec<std::boxed::Box<dyn Any>>,
+}
+
+/// Слабая ссылка на [`ModuleSources`] для регистрации источников из асинхронных задач
+#[derive(Clone)]
+pub struct WeakModuleSources(Weak<RefCell<SourcesInner>>);
+
+impl ModuleSources {
+    /// Регистрирует источник, который должен жить вместе с модулем
+    pub fn push(&self, source: SourceId) {
+        self.0.borrow_mut().sources.push(source);
+    }
+
+    /// Удерживает объект (например, подписку на события), пока жив модуль
+    pub fn hold(&self, guard: impl Any) {
+        self.0.borrow_mut().guards.push(std::boxed::Box::new(guard));
+    }
+
+    /// Слабая ссылка для регистрации источников после завершения асинхронной инициализации
+    pub fn downgrade(&self) -> WeakModuleSources {
+        WeakModuleSources(Rc::downgrade(&self.0))
+    }
+
+    /// Отключает все зарегистрированные источники и снимает подписки
+    pub fn clear(&self) {
+        let (sources, guards) = {
+            let mut inner = self.0.borrow_mut();
+            (std::mem::take(&mut inner.sources), std::mem::take(&mut inner.guards))
+        };
+        for source in sources {
+            source.remove();
+        }
+        drop(guards);
+    }
+}
+
+impl Drop for ModuleSources {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl WeakModuleSources {
+    /// Регистрирует источник; если модуль уже удален — сразу отключает его
+    pub fn push(&self, source: SourceId) {
+        match self.0.upgrade() {
+            Some(inner) => inner.borrow_mut().sources.push(source),
+            None => source.remove(),
+        }
+    }
+}
+
+/// Прижимает виджет к концу секции с отступом `margin` вдоль направления бара
+pub fn align_end(widget: &impl IsA<Widget>, orientation: Orientation, margin: i32) {
+    match orientation {
+        Orientation::Vertical => {
+            widget.set_halign(gtk4::Align::Center);
+            widget.set_valign(gtk4::Align::End);
+            widget.set_margin_bottom(margin);
+        }
+        _ => {
+            widget.set_halign(gtk4::Align::End);
+            widget.set_margin_end(margin);
+        }
+    }
+}
+
+/// Переводит виджет модуля в состояние ошибки (класс `.error` и подсказка с текстом)
+/// или снимает его, если `error` равен `None`
+pub fn set_error(widget: &impl IsA<Widget>, error: Option<&str>) {
+    match error {
+        Some(message) => {
+            widget.add_css_class(ERROR_CSS_CLASS);
+            widget.set_tooltip_text(Some(message));
+        }
+        None => {
+            if widget.has_css_class(ERROR_CSS_CLASS) {
+                widget.remove_css_class(ERROR_CSS_CLASS);
+                widget.set_tooltip_text(None);
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use gtk4::prelude::*;
+
+use super::module::{BoxedModule, ModuleContext, MODULE_CSS_CLASS};
+use super::{ClockComponent, LangComponent, TrayComponent, WorkspacesComponent};
+
+/// Фабрика модуля
+type ModuleFactory = fn(&ModuleContext) -> BoxedModule;
+
+/// Реестр доступных модулей: имя в конфигурации → фабрика.
+///
+/// Чтобы добавить новый модуль, достаточно реализовать [`super::BarModule`]
+/// и зарегистрировать его здесь.
+const MODULES: &[(&str, ModuleFactory)] = &[
+    ("workspaces", |ctx| Box::new(WorkspacesComponent::new(ctx))),
+    ("tray", |ctx| Box::new(TrayComponent::new(ctx))),
+    ("lang", |ctx| Box::new(LangComponent::new(ctx))),
+    ("clock", |ctx| Box::new(ClockComponent::new(ctx))),
+];
+
+/// Создает модуль по имени из конфигурации.
+///
+/// Корневой виджет модуля получает общие CSS классы `.module` и `.module-<name>`.
+/// Возвращает `None` для неизвестного имени.
+pub fn build_module(name: &str, ctx: &ModuleContext) -> Option<BoxedModule> {
+    let Some((_, factory)) = MODULES.iter().find(|(module_name, _)| *module_name == name) else {
+        logger::log_warning(
+            "ModuleRegistry",
+            format!("Unknown module '{name}', available: {}", module_names().collect::<Vec<_>>().join(", ")),
+        );
+        return None;
+    };
+
+    let module = factory(ctx);
+    let widget = module.widget();
+    widget.add_css_class(MODULE_CSS_CLASS);
+    widget.add_css_class(&format!("{MODULE_CSS_CLASS}-{name}"));
+    Some(module)
+}
+
+/// Имена всех зарегистрированных модулей
+pub fn module_names() -> impl Iterator<Item = &'static str> {
+    MODULES.iter().map(|(name, _)| *name)
+}
@@ -1,38 +1,105 @@
-use gtk4::{Box, Image, Label, GestureClick, PopoverMenu, gdk::Rectangle, prelude::*};
+use gtk4::{Box, Image, Label, GestureClick, PopoverMenu, Widget, gdk::Rectangle, prelude::*};
 use gtk4::gio::{Menu, SimpleAction, SimpleActionGroup};
 use gdk_pixbuf::Pixbuf;
-use glib::MainContext;
-use std::{rc::Rc, cell::RefCell, collections::HashMap};
+use glib::{MainContext, timeout_add_local, ControlFlow};
+use std::{rc::Rc, cell::RefCell, collections::HashMap, time::Duration};
 use tray::{MenuNode, Tray, TrayItem};
 use zvariant::{OwnedValue, Value};
 
 use crate::config::BarConfig;
+use super::module::{BarModule, ModuleContext, ModuleSources, WeakModuleSources, align_end, set_error};
 
 /// Компонент для отображения системного трея
 pub struct TrayComponent {
+    container: Box,
+    sources: ModuleSources,
+}
+
+impl TrayComponent {
+    /// Создает новый компонент tray
+    pub fn new(ctx: &ModuleContext) -> Self {
+        let container = Box::new(ctx.orientation(), 6);
+        container.add_css_class("tray");
+        align_end(&container, ctx.orientation(), 12);
+
+        Self {
+            container,
+            sources: ModuleSources::default(),
+        }
+    }
+
+    fn start_tray_updater(view: TrayView, sources: &WeakModuleSources) {
+        let tray = view.tray.clone();
+        let view = Rc::new(view);
+
+        let source = timeout_add_local(Duration::from_secs(view.config.tray_update_interval_secs), move || {
+            let tray_ref = tray.clone();
+            let view_ref = view.clone();
+
+            MainContext::default().spawn_local(async move {
+                let items = match tray_ref.get_items().await {
+                    Ok(items) => items,
+                    Err(e) => {
+                        logger::log_error("TrayUpdater", e);
+                        return;
+                    }
+                };
+                view_ref.refresh(&items);
+            });
+            ControlFlow::Continue
+        });
+        sources.push(source);
+    }
+}
+
+impl BarModule for TrayComponent {
+    fn widget(&self) -> Widget {
+        self.container.clone().upcast()
+    }
+
+    fn start(&mut self, ctx: &ModuleContext) {
+        // Инициализация tray
+        let container = self.container.clone();
+        let config = ctx.config.clone();
+        let sources = self.sources.downgrade();
+        MainContext::default().spawn_local(async move {
+            match Tray::new().await {
+                Ok(tray) => {
+                    set_error(&container, None);
+                    let view = TrayView::new(container, config, Rc::new(tray));
+                    Self::start_tray_updater(view, &sources);
+                }
+                Err(e) => {
+                    set_error(&container, Some(&e.to_string()));
+                    logger::log_error("TrayInitialization", e);
+                }
+            }
+        });
+    }
+
+    fn on_config(&mut self, ctx: &ModuleContext) {
+        // Новый набор источников: обновления от предыдущего запуска отключаются,
+        // даже если его инициализация еще не завершилась
+        self.sources = ModuleSources::default();
+        self.start(ctx);
+    }
+}
+
+/// Отрисовка элементов трея и их контекстных меню
+struct TrayView {
     container: Box,
     root: Box,
     config: BarConfig,
-    tray: Rc<RefCell<Tray>>,
+    tray: Rc<Tray>,
     popovers: Rc<RefCell<HashMap<u64, PopoverMenu>>>,
     popover_counter: Rc<RefCell<u64>>,
 }
 
-impl TrayComponent {
-    /// Создает новый компонент tray
-    pub fn new(
-        container: Box,
-        root: Box,
-        config: BarConfig,
-        tray: Rc<RefCell<Tray>>,
-    ) -> Self {
-        container.add_css_class("tray");
-        container.set_halign(gtk4::Align::End);
-        container.set_margin_end(12);
-        
+impl TrayView {
+    fn new(container: Box, config: BarConfig, tray: Rc<Tray>) -> Self {
         Self {
+            root: container.clone(),
             container,
-            root,
             config,
             tray,
             popovers: Rc::new(RefCell::new(HashMap::new())),
@@ -41,7 +108,7 @@ impl TrayComponent {
     }
 
     /// Обновляет содержимое трея
-    pub fn refresh(&self, items: &[TrayItem]) {
+    fn refresh(&self, items: &[TrayItem]) {
         self.clear_children();
 
         for item in items {
@@ -195,10 +262,7 @@ impl TrayComponent {
             
             MainContext::default().spawn_local(async move {
                 let item_clone = item_ref.clone();
-                let menu_result = {
-                    #[allow(clippy::await_holding_refcell_ref)]
-                    tray_ref.borrow().get_item_menu(&item_clone).await
-                };
+                let menu_result = tray_ref.get_item_menu(&item_clone).await;
                 
                 match menu_result {
                     Ok(Some(menu_node)) => {
@@ -214,7 +278,7 @@ impl TrayComponent {
                         // Привязываем action group к root виджету
                         root_ref.insert_action_group("tray", Some(&action_group));
                         
-                        let popover = Self::build_popup_menu(&menu_node, &item_ref);
+                        let popover = Self::build_popup_menu(&menu_node);
                         popover.add_css_class("tray-menu");
                         
                         // Устанавливаем фон программно для надежности
@@ -272,12 +336,12 @@ impl TrayComponent {
         widget.add_controller(click);
     }
 
-    fn build_popup_menu(node: &MenuNode, item: &TrayItem) -> PopoverMenu {
-        let gio_menu = Self::build_gio_menu(node, item);
+    fn build_popup_menu(node: &MenuNode) -> PopoverMenu {
+        let gio_menu = Self::build_gio_menu(node);
         PopoverMenu::from_model(Some(&gio_menu))
     }
 
-    fn build_gio_menu(node: &MenuNode, item: &TrayItem) -> Menu {
+    fn build_gio_menu(node: &MenuNode) -> Menu {
         let menu = Menu::new();
         
         let children_count = node.children.len();
@@ -311,7 +375,7 @@ impl TrayComponent {
                 let separator_item = gtk4::gio::MenuItem::new(Some(""), Some(&separator_action));
                 menu.append_item(&separator_item);
             } else if !child.children.is_empty() {
-                let submenu = Self::build_gio_menu(child, item);
+                let submenu = Self::build_gio_menu(child);
                 menu.append_submenu(Some(&label), &submenu);
             } else {
                 let action_name = format!("tray.item.{}", child.id);
@@ -342,7 +406,7 @@ impl TrayComponent {
         node: &MenuNode,
         action_group: &SimpleActionGroup,
         item: TrayItem,
-        tray: Rc<RefCell<Tray>>,
+        tray: Rc<Tray>,
     ) {
         for child in &node.children {
             let child_id = child.id;
@@ -385,8 +449,7 @@ impl TrayComponent {
                     if let Some(menu_path) = &item_ref.menu_path {
                         let menu_path_str = menu_path.as_str();
                         if menu_path_str != "/" && !menu_path_str.is_empty() {
-                            let tray_borrow = tray_ref.borrow();
-                            let connection = tray_borrow.connection();
+                            let connection = tray_ref.connection();
                             let bus_name = item_ref.bus_name.clone();
                             
                             match tray::DBusMenuProxy::builder(connection)
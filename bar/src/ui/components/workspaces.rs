@@ -1,117 +1,721 @@
-use gtk4::{Box, Image, Label, Orientation, EventControllerMotion, GestureClick, PropagationPhase, prelude::*};
-use hyprland::dispatch::{Dispatch, DispatchType, WorkspaceIdentifierWithSpecial};
-use hyprland_workspaces::HyprWorkspaces;
+use glib::MainContext;
+use gtk4::{
+    Align, Box, DragSource, DropTarget, EventControllerKey, EventControllerMotion, EventControllerScroll,
+    EventControllerScrollFlags, EventSequenceState, GestureClick, Image, Label, Orientation, Overlay, PropagationPhase, Widget,
+    WidgetPaintable,
+    gdk::{BUTTON_MIDDLE, BUTTON_PRIMARY, ContentProvider, DragAction, Key},
+    glib::{Propagation, Type},
+    prelude::*,
+};
+use hyprland::{
+    dispatch::{Dispatch, DispatchType, MonitorIdentifier, WindowIdentifier, WorkspaceIdentifierWithSpecial},
+    shared::Address,
+};
+use hyprland_workspaces::{HyprWorkspace, HyprWorkspaces, HyprlandClient};
+use std::{cell::RefCell, collections::{HashMap, HashSet}, rc::Rc};
 
-use crate::config::BarConfig;
+use crate::config::{BarConfig, ScrollMode};
+use crate::services::{HyprlandEvent, Worker};
+use super::module::{BarModule, ModuleContext, ModuleSources, set_error};
 
 /// Компонент для отображения и управления workspace'ами Hyprland
 pub struct WorkspacesComponent {
-    container: Box,
-    config: BarConfig,
+    view: Rc<RefCell<WorkspacesView>>,
+    sources: ModuleSources,
 }
 
 impl WorkspacesComponent {
     /// Создает новый компонент workspace
-    pub fn new(container: Box, config: BarConfig) -> Self {
+    pub fn new(ctx: &ModuleContext) -> Self {
+        let container = Box::new(ctx.orientation(), ctx.config.spacing);
         container.add_css_class("workspaces");
-        Self { container, config }
+
+        let status = Label::new(None);
+        status.set_opacity(0.8);
+        status.set_visible(false);
+        container.append(&status);
+
+        let view = Rc::new(RefCell::new(WorkspacesView {
+            container,
+            status,
+            config: ctx.config.clone(),
+            monitor: ctx.monitor.clone(),
+            worker: ctx.worker.clone(),
+            entries: HashMap::new(),
+            loading: false,
+            stale: false,
+            open: Vec::new(),
+            active_id: None,
+            monitor_active_id: None,
+            urgent: HashSet::new(),
+        }));
+        Self::add_scroll(&view);
+
+        Self {
+            view,
+            sources: ModuleSources::default(),
+        }
+    }
+
+    /// Переключение workspace'ов колесом мыши над контейнером
+    fn add_scroll(view: &Rc<RefCell<WorkspacesView>>) {
+        let scroll = EventControllerScroll::new(
+            EventControllerScrollFlags::BOTH_AXES | EventControllerScrollFlags::DISCRETE,
+        );
+        let weak = Rc::downgrade(view);
+        scroll.connect_scroll(move |_, dx, dy| {
+            let Some(view) = weak.upgrade() else {
+                return Propagation::Proceed;
+            };
+            let delta = if dy != 0.0 { dy } else { dx };
+            if delta == 0.0 {
+                return Propagation::Proceed;
+            }
+
+            let view = view.borrow();
+            let step = if (delta > 0.0) != view.config.workspaces.scroll_reverse { 1 } else { -1 };
+            if let Some(target) = view.scroll_target(step) {
+                dispatch(&view.worker, "WorkspaceScroll", move || Dispatch::call(DispatchType::Workspace(target)));
+            }
+            Propagation::Stop
+        });
+        view.borrow().container.add_controller(scroll);
+    }
+
+    /// Запрашивает снимок workspace'ов в фоновом потоке и отрисовывает его.
+    ///
+    /// Пока запрос выполняется, новые запросы не отправляются: после ответа
+    /// выполняется ровно один повторный запрос, если были события.
+    fn request_refresh(view: &Rc<RefCell<WorkspacesView>>, worker: &Worker) {
+        {
+            let mut view = view.borrow_mut();
+            if view.loading {
+                view.stale = true;
+                return;
+            }
+            view.loading = true;
+        }
+
+        let rules = view.borrow().config.workspaces.rules.clone();
+        let weak = Rc::downgrade(view);
+        let worker = worker.clone();
+        MainContext::default().spawn_local(async move {
+            let snapshot = worker.run(move || HyprWorkspaces::init(&rules)).await;
+            let Some(view) = weak.upgrade() else {
+                return;
+            };
+            let stale = {
+                let mut view = view.borrow_mut();
+                view.loading = false;
+                match snapshot {
+                    Some(result) => view.apply(result),
+                    None => view.show_status("Hyprland: worker unavailable"),
+                }
+                std::mem::take(&mut view.stale)
+            };
+            if stale {
+                Self::request_refresh(&view, &worker);
+            }
+        });
     }
+}
 
-    /// Обновляет содержимое workspace'ов
-    pub fn refresh(&self) {
-        self.clear_children();
+impl BarModule for WorkspacesComponent {
+    fn widget(&self) -> Widget {
+        self.view.borrow().container.clone().upcast()
+    }
 
-        match HyprWorkspaces::init() {
-            Ok(workspaces) if !workspaces.map.is_empty() => {
-                let mut entries: Vec<_> = workspaces.map.iter().collect();
-                entries.sort_by_key(|(id, _)| *id);
+    fn start(&mut self, ctx: &ModuleContext) {
+        Self::request_refresh(&self.view, &ctx.worker);
 
-                for (id, ws) in entries {
-                    let ws_box = self.create_workspace_widget(*id, ws, workspaces.active_id);
-                    self.container.append(&ws_box);
+        // Обновление по событиям Hyprland
+        let view = self.view.clone();
+        let worker = ctx.worker.clone();
+        self.sources.hold(ctx.hyprland_events.subscribe(move |event| match event {
+            // Заголовок обновляется в подсказке без запроса снимка, если от него
+            // не зависят правила для окон
+            HyprlandEvent::WindowTitleChanged { address, title } => {
+                let match_titles = {
+                    let view = view.borrow();
+                    view.set_title(&address.to_string(), title);
+                    view.config.workspaces.rules.match_titles()
+                };
+                if match_titles {
+                    Self::request_refresh(&view, &worker);
+                }
+            }
+            // Запрос внимания приходит только событием, в снимке его нет
+            HyprlandEvent::UrgentStateChanged { address } => {
+                view.borrow_mut().urgent.insert(address.to_string());
+                Self::request_refresh(&view, &worker);
+            }
+            // Самое частое событие: фокус меняется без запроса снимка
+            HyprlandEvent::ActiveWindowChanged { address } => {
+                let address = address.as_ref().map(|address| address.to_string());
+                let mut view = view.borrow_mut();
+                view.set_active_window(address.as_deref());
+                // Выполняемый запрос мог получить снимок до смены фокуса
+                if view.loading {
+                    view.stale = true;
                 }
             }
+            // Раскладка клавиатуры на workspace'ы не влияет
+            HyprlandEvent::LayoutChanged { .. } => {}
+            _ => Self::request_refresh(&view, &worker),
+        }));
+    }
+
+    fn on_config(&mut self, ctx: &ModuleContext) {
+        self.sources.clear();
+        {
+            let mut view = self.view.borrow_mut();
+            view.container.set_spacing(ctx.config.spacing);
+            view.container.set_orientation(ctx.orientation());
+            view.config = ctx.config.clone();
+            view.monitor = ctx.monitor.clone();
+            view.worker = ctx.worker.clone();
+            // Размер иконок и ориентация заданы при создании виджетов
+            view.clear_entries();
+        }
+        self.start(ctx);
+    }
+
+    fn on_icons_changed(&mut self, ctx: &ModuleContext) {
+        // Пути к иконкам ищутся при запросе снимка; иконки с новым путем пересоздаются
+        Self::request_refresh(&self.view, &ctx.worker);
+    }
+}
+
+/// Отрисовка workspace'ов в контейнере.
+///
+/// Виджеты хранятся по id workspace'а и адресу окна: при обновлении
+/// добавляется, удаляется и переставляется только то, что изменилось.
+struct WorkspacesView {
+    container: Box,
+    /// Сообщение вместо списка (нет workspace'ов, ошибка запроса)
+    status: Label,
+    config: BarConfig,
+    /// Коннектор монитора бара
+    monitor: Option<String>,
+    /// Фоновый поток для запросов и диспатчей Hyprland
+    worker: Worker,
+    entries: HashMap<i32, WorkspaceEntry>,
+    /// Запрос снимка выполняется в фоновом потоке
+    loading: bool,
+    /// Во время запроса пришли события — нужен повторный запрос
+    stale: bool,
+    /// Открытые обычные workspace'ы (id, монитор) из последнего снимка, по возрастанию id
+    open: Vec<(i32, String)>,
+    /// Глобально активный workspace
+    active_id: Option<i32>,
+    /// Активный workspace монитора бара
+    monitor_active_id: Option<i32>,
+    /// Адреса окон, запросивших внимание, пока их workspace не посещен
+    urgent: HashSet<String>,
+}
+
+/// Виджет одного workspace'а
+struct WorkspaceEntry {
+    root: Box,
+    title: Label,
+    clients_box: Box,
+    /// Прочерк для workspace'а без окон
+    empty: Label,
+    /// Индикатор «+N» для окон сверх лимита иконок
+    more: Label,
+    /// Подсказка со списком окон
+    tooltip: Rc<RefCell<WorkspaceTooltip>>,
+    /// Действие при клике, Enter и перетаскивании окна
+    target: Rc<WorkspaceTarget>,
+    clients: HashMap<String, ClientEntry>,
+}
+
+/// Подсказка workspace'а.
+///
+/// Виджет строится, когда меняется список окон, и отдается подсказке
+/// при каждом запросе: GTK запрашивает ее на каждое движение указателя.
+#[derive(Default)]
+struct WorkspaceTooltip {
+    rows: Vec<TooltipRow>,
+    widget: Option<Box>,
+    /// Подписи заголовков по адресу окна; заголовок меняется без перестройки
+    titles: HashMap<String, Label>,
+}
+
+impl WorkspaceTooltip {
+    fn set_rows(&mut self, rows: Vec<TooltipRow>) {
+        if rows == self.rows {
+            return;
+        }
+        self.titles.clear();
+        self.widget = (!rows.is_empty()).then(|| create_tooltip(&rows, &mut self.titles));
+        self.rows = rows;
+    }
+
+    /// Обновляет заголовок окна; `false`, если окна нет в подсказке
+    fn set_title(&mut self, address: &str, title: &str) -> bool {
+        let Some(row) = self.rows.iter_mut().find(|row| row.address == address) else {
+            return false;
+        };
+        row.title = title.to_string();
+        if let Some(label) = self.titles.get(address) {
+            set_tooltip_title(label, title);
+        }
+        true
+    }
+}
+
+/// Окно в подсказке workspace'а
+#[derive(PartialEq)]
+struct TooltipRow {
+    address: String,
+    icon: Option<String>,
+    name: String,
+    title: String,
+}
+
+/// Иконка окна или группы окон одного класса; пересоздается, только если сменилась иконка
+struct ClientEntry {
+    widget: Widget,
+    /// Число окон в группе
+    badge: Label,
+    icon: Option<String>,
+    windows: Rc<RefCell<ClientWindows>>,
+}
+
+/// Окна за одной иконкой; общие с обработчиками клика и перетаскивания
+#[derive(Default)]
+struct ClientWindows {
+    addresses: Vec<String>,
+    /// Индекс окна в фокусе
+    focused: Option<usize>,
+}
+
+impl ClientWindows {
+    /// Окно, к которому относятся закрытие и перетаскивание: в фокусе или первое
+    fn current(&self) -> Option<&str> {
+        let index = self.focused.unwrap_or(0);
+        self.addresses.get(index).map(String::as_str)
+    }
+
+    /// Окно для фокуса по клику: следующее за окном в фокусе, чтобы клики перебирали группу
+    fn next(&self) -> Option<&str> {
+        let index = self.focused.map_or(0, |index| (index + 1) % self.addresses.len().max(1));
+        self.addresses.get(index).map(String::as_str)
+    }
+}
+
+impl WorkspacesView {
+    /// Отрисовывает снимок workspace'ов
+    fn apply(&mut self, snapshot: anyhow::Result<HyprWorkspaces>) {
+        match snapshot {
+            Ok(workspaces) if !workspaces.map.is_empty() || self.has_persistent() => {
+                self.status.set_visible(false);
+                set_error(&self.container, None);
+                self.update(&workspaces);
+            }
             Ok(_) => {
-                let placeholder = Label::new(Some("Hyprland: no active workspaces"));
-                placeholder.set_opacity(0.8);
-                self.container.append(&placeholder);
+                set_error(&self.container, None);
+                self.show_status("Hyprland: no active workspaces");
             }
             Err(err) => {
-                let error_label = Label::new(Some(&format!("Hyprland: request error ({err})")));
-                error_label.set_opacity(0.8);
-                self.container.append(&error_label);
+                set_error(&self.container, Some(&err.to_string()));
+                self.show_status(&format!("Hyprland: request error ({err})"));
             }
         }
     }
 
-    fn create_workspace_widget(
-        &self,
-        id: i32,
-        ws: &hyprland_workspaces::HyprWorkspace,
-        active_id: Option<i32>,
-    ) -> Box {
-        let ws_box = Box::new(Orientation::Horizontal, 6);
-        ws_box.add_css_class("workspace");
-        ws_box.set_margin_start(4);
-        ws_box.set_margin_end(4);
-        
-        if Some(id) == active_id {
-            ws_box.add_css_class("active");
+    fn update(&mut self, workspaces: &HyprWorkspaces) {
+        let monitor = self.filter_monitor().map(str::to_string);
+        let show_special = self.config.workspaces.show_special;
+        let mut visible: Vec<&HyprWorkspace> = workspaces
+            .map
+            .values()
+            .filter(|ws| monitor.as_deref().is_none_or(|monitor| ws.monitor == monitor))
+            .filter(|ws| show_special || !ws.special)
+            .collect();
+
+        // Постоянные workspace'ы, которых еще нет в Hyprland, показываются пустыми
+        let placeholders: Vec<HyprWorkspace> = self
+            .config
+            .workspaces
+            .persistent_workspaces
+            .for_monitor(monitor.as_deref())
+            .into_iter()
+            .filter(|id| !workspaces.map.contains_key(id))
+            .map(|id| HyprWorkspace::empty(id, monitor.as_deref().unwrap_or_default()))
+            .collect();
+        visible.extend(&placeholders);
+
+        // Special workspace'ы идут после обычных
+        visible.sort_by_key(|ws| (ws.special, ws.id));
+
+        // Активный workspace свой у каждого монитора
+        let active: Vec<i32> = match monitor.as_deref() {
+            Some(monitor) => workspaces.active_on(monitor).into_iter().collect(),
+            None if workspaces.monitor_active.is_empty() => workspaces.active_id.into_iter().collect(),
+            None => workspaces.monitor_active.values().copied().collect(),
+        };
+        // Открытый special workspace показывается поверх активного
+        let overlay: Vec<i32> = match monitor.as_deref() {
+            Some(monitor) => workspaces.special_on(monitor).into_iter().collect(),
+            None => workspaces.monitor_special.values().copied().collect(),
+        };
+
+        let mut open: Vec<(i32, String)> = workspaces
+            .map
+            .values()
+            .filter(|ws| !ws.special)
+            .map(|ws| (ws.id, ws.monitor.clone()))
+            .collect();
+        open.sort_unstable();
+        self.open = open;
+        self.active_id = workspaces.active_id;
+        self.monitor_active_id = match self.monitor.as_deref() {
+            Some(monitor) => workspaces.active_on(monitor),
+            None => workspaces.active_id,
+        };
+
+        self.clear_visited_urgent(workspaces);
+
+        let container = self.container.clone();
+        self.entries.retain(|id, entry| {
+            let keep = visible.iter().any(|ws| ws.id == *id);
+            if !keep {
+                container.remove(&entry.root);
+            }
+            keep
+        });
+
+        let mut order = vec![self.status.clone().upcast::<Widget>()];
+        for ws in visible {
+            if !self.entries.contains_key(&ws.id) {
+                let entry = self.create_workspace_entry(ws);
+                self.container.append(&entry.root);
+                self.entries.insert(ws.id, entry);
+            }
+            let title = self.title(ws);
+            let Some(entry) = self.entries.get_mut(&ws.id) else {
+                continue;
+            };
+
+            // Workspace мог быть переименован
+            if entry.title.text() != title {
+                entry.title.set_text(&title);
+            }
+
+            set_class(&entry.root, "active", active.contains(&ws.id));
+            set_class(&entry.root, "overlay", overlay.contains(&ws.id));
+            let placeholder = !workspaces.map.contains_key(&ws.id);
+            set_class(&entry.root, "empty", placeholder);
+            // Несуществующий workspace создается на мониторе бара, а не на мониторе в фокусе
+            entry.target.create_on(placeholder.then(|| monitor.clone()).flatten());
+            let urgent = ws.clients.iter().any(|client| self.urgent.contains(&client.address));
+            set_class(&entry.root, "urgent", urgent);
+            Self::update_clients(entry, &ws.clients, &self.config, &self.worker, workspaces.active_window.as_deref());
+            entry.tooltip.borrow_mut().set_rows(
+                ws.clients
+                    .iter()
+                    .map(|client| TooltipRow {
+                        address: client.address.clone(),
+                        icon: client.icons.first().cloned(),
+                        name: client.name.clone(),
+                        title: client.title.clone(),
+                    })
+                    .collect(),
+            );
+            order.push(entry.root.clone().upcast());
         }
-        
-        self.add_hover(&ws_box);
-        self.add_click_switch(&ws_box, id);
+        reorder_children(&self.container, &order);
+    }
 
-        let title = Label::new(Some(&format!("{}:", id)));
-        title.add_css_class("title");
-        ws_box.append(&title);
+    /// Снимает запрос внимания с окон, чей workspace посещен, и с закрытых окон
+    fn clear_visited_urgent(&mut self, workspaces: &HyprWorkspaces) {
+        if self.urgent.is_empty() {
+            return;
+        }
+        let visited: HashSet<i32> = workspaces
+            .monitor_active
+            .values()
+            .copied()
+            .chain(workspaces.active_id)
+            .collect();
+        let existing: HashMap<&str, i32> = workspaces
+            .map
+            .values()
+            .flat_map(|ws| ws.clients.iter().map(move |client| (client.address.as_str(), ws.id)))
+            .collect();
+        self.urgent.retain(|address| {
+            existing
+                .get(address.as_str())
+                .is_some_and(|id| !visited.contains(id))
+                && workspaces.active_window.as_ref() != Some(address)
+        });
+    }
 
-        if ws.clients.is_empty() {
-            let empty = Label::new(Some("—"));
-            empty.set_opacity(0.6);
-            ws_box.append(&empty);
-        } else {
-            for client in &ws.clients {
-                let client_widget = self.create_client_widget(client);
-                ws_box.append(&client_widget);
+    fn update_clients(
+        entry: &mut WorkspaceEntry,
+        clients: &[HyprlandClient],
+        config: &BarConfig,
+        worker: &Worker,
+        active_window: Option<&str>,
+    ) {
+        let groups = group_clients(clients, config.workspaces.group_clients);
+        let limit = config.workspaces.max_icons.unwrap_or(usize::MAX);
+        let (shown, hidden) = groups.split_at(limit.min(groups.len()));
+
+        entry.clients.retain(|key, client| {
+            let keep = shown
+                .iter()
+                .any(|group| &group.key == key && group.icon() == client.icon.as_ref());
+            if !keep {
+                entry.clients_box.remove(&client.widget);
+            }
+            keep
+        });
+
+        let mut order = vec![entry.empty.clone().upcast::<Widget>()];
+        for group in shown {
+            let client_entry = entry.clients.entry(group.key.clone()).or_insert_with(|| {
+                let client_entry = create_client_entry(group.clients[0], config, worker);
+                entry.clients_box.append(&client_entry.widget);
+                client_entry
+            });
+
+            let focused = group
+                .clients
+                .iter()
+                .position(|client| active_window == Some(client.address.as_str()));
+            *client_entry.windows.borrow_mut() = ClientWindows {
+                addresses: group.clients.iter().map(|client| client.address.clone()).collect(),
+                focused,
+            };
+            set_class(&client_entry.widget, "focused", focused.is_some());
+            client_entry.badge.set_text(&group.clients.len().to_string());
+            client_entry.badge.set_visible(group.clients.len() > 1);
+            order.push(client_entry.widget.clone());
+        }
+
+        // Окна сверх лимита иконок
+        let hidden_count: usize = hidden.iter().map(|group| group.clients.len()).sum();
+        entry.more.set_visible(hidden_count > 0);
+        if hidden_count > 0 {
+            entry.more.set_text(&format!("+{hidden_count}"));
+            let classes: Vec<&str> = hidden.iter().map(|group| group.clients[0].class.as_str()).collect();
+            entry.more.set_tooltip_text(Some(&classes.join(", ")));
+        }
+        order.push(entry.more.clone().upcast());
+
+        entry.empty.set_visible(clients.is_empty());
+        reorder_children(&entry.clients_box, &order);
+    }
+
+    /// Обновляет заголовок окна в подсказке его workspace'а
+    fn set_title(&self, address: &str, title: &str) {
+        for entry in self.entries.values() {
+            if entry.tooltip.borrow_mut().set_title(address, title) {
+                return;
+            }
+        }
+    }
+
+    /// Отмечает окно в фокусе и снимает с него запрос внимания
+    fn set_active_window(&mut self, address: Option<&str>) {
+        let was_urgent = address.is_some_and(|address| self.urgent.remove(address));
+        for entry in self.entries.values() {
+            for client in entry.clients.values() {
+                let mut windows = client.windows.borrow_mut();
+                windows.focused = windows.addresses.iter().position(|window| Some(window.as_str()) == address);
+                set_class(&client.widget, "focused", windows.focused.is_some());
+            }
+            if was_urgent {
+                let urgent = entry.tooltip.borrow().rows.iter().any(|row| self.urgent.contains(&row.address));
+                set_class(&entry.root, "urgent", urgent);
+            }
+        }
+    }
+
+    /// Скрывает workspace'ы и показывает сообщение
+    fn show_status(&mut self, text: &str) {
+        self.clear_entries();
+        self.status.set_text(text);
+        self.status.set_visible(true);
+    }
+
+    /// Удаляет все виджеты workspace'ов; следующее обновление создаст их заново
+    fn clear_entries(&mut self) {
+        for (_, entry) in self.entries.drain() {
+            self.container.remove(&entry.root);
+        }
+    }
+
+    /// Куда переключиться при прокрутке на `step`.
+    ///
+    /// `None`, если прокрутка отключена или без зацикливания уперлась в крайний workspace.
+    fn scroll_target(&self, step: i32) -> Option<WorkspaceIdentifierWithSpecial<'static>> {
+        let config = &self.config.workspaces;
+        match (config.scroll, self.monitor.as_deref()) {
+            (ScrollMode::None, _) => None,
+            // Hyprland применяет `m±1` к монитору в фокусе, а бар может быть на другом:
+            // следующий workspace монитора бара вычисляется здесь
+            (ScrollMode::Monitor, Some(monitor)) => {
+                let ids: Vec<i32> = self.open.iter().filter(|(_, m)| m == monitor).map(|(id, _)| *id).collect();
+                step_through(&ids, self.monitor_active_id, step, config.scroll_wrap)
+                    .map(WorkspaceIdentifierWithSpecial::Id)
+            }
+            (ScrollMode::Monitor, None) => Some(WorkspaceIdentifierWithSpecial::RelativeMonitor(step)),
+            (ScrollMode::All, _) => {
+                if config.scroll_wrap {
+                    return Some(WorkspaceIdentifierWithSpecial::RelativeOpen(step));
+                }
+                let ids: Vec<i32> = self.open.iter().map(|(id, _)| *id).collect();
+                let Some(position) = self.active_id.and_then(|current| ids.iter().position(|id| *id == current)) else {
+                    return Some(WorkspaceIdentifierWithSpecial::RelativeOpen(step));
+                };
+                let at_edge = if step > 0 { position + 1 >= ids.len() } else { position == 0 };
+                (!at_edge).then_some(WorkspaceIdentifierWithSpecial::RelativeOpen(step))
             }
         }
+    }
+
+    /// Есть ли постоянные workspace'ы для монитора бара
+    fn has_persistent(&self) -> bool {
+        !self
+            .config
+            .workspaces
+            .persistent_workspaces
+            .for_monitor(self.filter_monitor())
+            .is_empty()
+    }
 
-        ws_box
+    /// Монитор, workspace'ы которого показываются; `None` — все мониторы
+    fn filter_monitor(&self) -> Option<&str> {
+        if self.config.workspaces.all_monitors {
+            return None;
+        }
+        self.monitor.as_deref()
     }
 
-    fn create_client_widget(&self, client: &hyprland_workspaces::HyprlandClient) -> gtk4::Widget {
-        if let Some(icon_path) = client.icons.first() {
-            let image = Image::from_file(icon_path);
-            image.set_pixel_size(self.config.icon_size);
-            image.set_margin_end(4);
-            let tooltip = client
-                .desktop_file
-                .as_ref()
-                .map(|df| df.name.clone())
-                .unwrap_or_else(|| client.class.clone());
-            image.set_tooltip_text(Some(&tooltip));
-            image.upcast()
+    fn create_workspace_entry(&self, ws: &HyprWorkspace) -> WorkspaceEntry {
+        // В вертикальном баре workspace'ы идут столбцом, иконки внутри — тоже
+        let vertical = self.config.position.is_vertical();
+        let orientation = if vertical { Orientation::Vertical } else { Orientation::Horizontal };
+        let ws_box = Box::new(orientation, 6);
+        if vertical {
+            ws_box.set_margin_top(4);
+            ws_box.set_margin_bottom(4);
         } else {
-            let fallback = Label::new(Some(&client.class));
-            fallback.set_margin_end(6);
-            fallback.set_opacity(0.8);
-            fallback.upcast()
+            ws_box.set_margin_start(4);
+            ws_box.set_margin_end(4);
+        }
+        ws_box.add_css_class("workspace");
+        if ws.special {
+            ws_box.add_css_class("special");
         }
+
+        let target = Rc::new(WorkspaceTarget::of(ws));
+        self.add_hover(&ws_box);
+        self.add_click_switch(&ws_box, &target);
+        self.add_key_switch(&ws_box, &target);
+        self.add_drop_target(&ws_box, &target);
+
+        let title = Label::new(Some(&self.title(ws)));
+        title.add_css_class("title");
+        ws_box.append(&title);
+
+        let clients_box = Box::new(orientation, 6);
+        ws_box.append(&clients_box);
+
+        let empty = Label::new(Some("—"));
+        empty.set_opacity(0.6);
+        clients_box.append(&empty);
+
+        let tooltip = Rc::new(RefCell::new(WorkspaceTooltip::default()));
+        self.add_tooltip(&ws_box, &tooltip);
+
+        let more = Label::new(None);
+        more.add_css_class("more");
+        more.set_visible(false);
+        clients_box.append(&more);
+
+        WorkspaceEntry {
+            root: ws_box,
+            title,
+            clients_box,
+            empty,
+            more,
+            tooltip,
+            target,
+            clients: HashMap::new(),
+        }
+    }
+
+    /// Подпись workspace'а по шаблону из конфигурации
+    fn title(&self, ws: &HyprWorkspace) -> String {
+        let name = match ws.special_name() {
+            Some(name) => name,
+            None if ws.special => "special",
+            None => &ws.name,
+        };
+        self.config
+            .workspaces
+            .label(ws.id, name, ws.special, self.config.position.is_vertical())
     }
 
-    fn add_click_switch(&self, widget: &Box, workspace_id: i32) {
+    fn add_click_switch(&self, widget: &Box, target: &Rc<WorkspaceTarget>) {
+        let target = target.clone();
+        let worker = self.worker.clone();
+        // Фаза bubble: клики по иконкам окон обрабатываются ими самими
         let click = GestureClick::new();
-        click.set_propagation_phase(PropagationPhase::Capture);
         click.set_button(0);
-        click.connect_released(move |_, _, _, _| {
-            if let Err(err) = Dispatch::call(DispatchType::Workspace(
-                WorkspaceIdentifierWithSpecial::Id(workspace_id),
-            )) {
-                logger::log_error("WorkspaceSwitch", err);
+        click.connect_released(move |_, _, _, _| target.activate(&worker));
+        widget.add_controller(click);
+    }
+
+    /// Переключение с клавиатуры: кнопка workspace'а получает фокус по Tab
+    /// и активируется по Enter или пробелу
+    fn add_key_switch(&self, widget: &Box, target: &Rc<WorkspaceTarget>) {
+        widget.set_focusable(true);
+        let target = target.clone();
+        let worker = self.worker.clone();
+        let keys = EventControllerKey::new();
+        keys.connect_key_pressed(move |_, key, _, _| match key {
+            Key::Return | Key::KP_Enter | Key::space => {
+                target.activate(&worker);
+                Propagation::Stop
             }
+            _ => Propagation::Proceed,
+        });
+        widget.add_controller(keys);
+    }
+
+    /// Окно, перетащенное на workspace, переносится в него без переключения
+    fn add_drop_target(&self, widget: &Box, target: &Rc<WorkspaceTarget>) {
+        let target = target.clone();
+        let worker = self.worker.clone();
+        let drop = DropTarget::new(Type::STRING, DragAction::MOVE);
+        drop.connect_drop(move |_, value, _, _| {
+            let Ok(address) = value.get::<String>() else {
+                return false;
+            };
+            target.move_window(&worker, address);
+            true
+        });
+        widget.add_controller(drop);
+    }
+
+    /// Подсказка со списком окон workspace'а: иконка, приложение и заголовок
+    fn add_tooltip(&self, widget: &Box, state: &Rc<RefCell<WorkspaceTooltip>>) {
+        widget.set_has_tooltip(true);
+        let state = state.clone();
+        widget.connect_query_tooltip(move |_, _, _, _, tooltip| {
+            let state = state.borrow();
+            let Some(content) = &state.widget else {
+                return false;
+            };
+            tooltip.set_custom(Some(content));
+            true
         });
-        widget.add_controller(click);
     }
 
     fn add_hover(&self, widget: &Box) {
@@ -131,11 +735,278 @@ impl WorkspacesComponent {
         });
         widget.add_controller(motion);
     }
+}
+
+/// Что происходит при активации workspace'а
+#[derive(Clone)]
+enum WorkspaceTarget {
+    /// Переключение на обычный workspace
+    Id {
+        id: i32,
+        /// Монитор, на котором создать workspace, если его еще нет в Hyprland
+        monitor: RefCell<Option<String>>,
+    },
+    /// Special workspace не переключается, а показывается/скрывается
+    Special(Option<String>),
+}
+
+impl WorkspaceTarget {
+    fn of(ws: &HyprWorkspace) -> Self {
+        if ws.special {
+            Self::Special(ws.special_name().map(str::to_string))
+        } else {
+            Self::Id { id: ws.id, monitor: RefCell::new(None) }
+        }
+    }
 
-    fn clear_children(&self) {
-        while let Some(child) = self.container.first_child() {
-            self.container.remove(&child);
+    /// Задает монитор для постоянного workspace'а, которого еще нет; `None` — workspace существует
+    fn create_on(&self, connector: Option<String>) {
+        if let Self::Id { monitor, .. } = self {
+            *monitor.borrow_mut() = connector;
         }
     }
+
+    fn activate(&self, worker: &Worker) {
+        match self {
+            Self::Id { id, monitor } => {
+                let id = *id;
+                let monitor = monitor.borrow().clone();
+                dispatch(worker, "WorkspaceSwitch", move || {
+                    // Hyprland создает workspace на мониторе в фокусе
+                    if let Some(monitor) = &monitor {
+                        Dispatch::call(DispatchType::FocusMonitor(MonitorIdentifier::Name(monitor)))?;
+                    }
+                    Dispatch::call(DispatchType::Workspace(WorkspaceIdentifierWithSpecial::Id(id)))
+                });
+            }
+            Self::Special(name) => {
+                let name = name.clone();
+                dispatch(worker, "WorkspaceSwitch", move || Dispatch::call(DispatchType::ToggleSpecialWorkspace(name)));
+            }
+        }
+    }
+
+    /// Переносит окно в этот workspace, не переключаясь на него
+    fn move_window(&self, worker: &Worker, address: String) {
+        let target = self.clone();
+        dispatch(worker, "WorkspaceMoveWindow", move || {
+            let workspace = match &target {
+                Self::Id { id, .. } => WorkspaceIdentifierWithSpecial::Id(*id),
+                Self::Special(name) => WorkspaceIdentifierWithSpecial::Special(name.as_deref()),
+            };
+            Dispatch::call(DispatchType::MoveToWorkspaceSilent(workspace, Some(window(&address))))
+        });
+    }
 }
 
+/// Workspace на `step` позиций от `current` в списке `ids`; с зацикливанием
+/// переходит через край, без него — `None` за краем. Если `current` нет
+/// в списке, прокрутка начинается с первого workspace'а
+fn step_through(ids: &[i32], current: Option<i32>, step: i32, wrap: bool) -> Option<i32> {
+    if ids.is_empty() {
+        return None;
+    }
+    let len = ids.len() as i32;
+    let next = match current.and_then(|current| ids.iter().position(|id| *id == current)) {
+        Some(position) => position as i32 + step,
+        None => 0,
+    };
+    let next = if wrap { next.rem_euclid(len) } else { next };
+    usize::try_from(next).ok().and_then(|next| ids.get(next)).copied()
+}
+
+/// Добавляет или снимает CSS класс
+fn set_class(widget: &impl IsA<Widget>, class: &str, enabled: bool) {
+    if enabled {
+        widget.add_css_class(class);
+    } else {
+        widget.remove_css_class(class);
+    }
+}
+
+/// Максимальная длина заголовка окна в подсказке
+const TOOLTIP_TITLE_MAX_CHARS: usize = 60;
+/// Размер иконок в подсказке
+const TOOLTIP_ICON_SIZE: i32 = 16;
+
+fn create_tooltip(rows: &[TooltipRow], titles: &mut HashMap<String, Label>) -> Box {
+    let list = Box::new(Orientation::Vertical, 4);
+    list.add_css_class("workspace-tooltip");
+    for row in rows {
+        let line = Box::new(Orientation::Horizontal, 6);
+        if let Some(icon) = &row.icon {
+            let image = Image::from_file(icon);
+            image.set_pixel_size(TOOLTIP_ICON_SIZE);
+            line.append(&image);
+        }
+
+        let name = Label::new(Some(&row.name));
+        name.add_css_class("name");
+        line.append(&name);
+
+        let title = Label::new(None);
+        title.add_css_class("window-title");
+        title.set_opacity(0.8);
+        set_tooltip_title(&title, &row.title);
+        line.append(&title);
+        titles.insert(row.address.clone(), title);
+        list.append(&line);
+    }
+    list
+}
+
+fn set_tooltip_title(label: &Label, title: &str) {
+    label.set_text(&truncate(title, TOOLTIP_TITLE_MAX_CHARS));
+    label.set_visible(!title.is_empty());
+}
+
+/// Обрезает строку до `max` символов с многоточием
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Окна, показанные одной иконкой
+struct ClientGroup<'a> {
+    /// Адрес окна или класс, если одинаковые окна группируются
+    key: String,
+    clients: Vec<&'a HyprlandClient>,
+}
+
+impl ClientGroup<'_> {
+    fn icon(&self) -> Option<&String> {
+        self.clients[0].icons.first()
+    }
+}
+
+/// Разбивает окна на группы с сохранением порядка; без группировки у каждого окна своя группа
+fn group_clients(clients: &[HyprlandClient], by_class: bool) -> Vec<ClientGroup<'_>> {
+    let mut groups: Vec<ClientGroup<'_>> = Vec::new();
+    for client in clients {
+        if by_class && let Some(group) = groups.iter_mut().find(|group| group.key == client.class) {
+            group.clients.push(client);
+            continue;
+        }
+        groups.push(ClientGroup {
+            key: if by_class { client.class.clone() } else { client.address.clone() },
+            clients: vec![client],
+        });
+    }
+    groups
+}
+
+/// Иконка со счетчиком окон поверх нее
+fn create_client_entry(client: &HyprlandClient, config: &BarConfig, worker: &Worker) -> ClientEntry {
+    let overlay = Overlay::new();
+    overlay.add_css_class("client");
+    overlay.set_child(Some(&create_client_icon(client, config)));
+
+    let badge = Label::new(None);
+    badge.add_css_class("count");
+    badge.set_halign(Align::End);
+    badge.set_valign(Align::End);
+    badge.set_visible(false);
+    overlay.add_overlay(&badge);
+
+    let widget: Widget = overlay.upcast();
+    let windows = Rc::new(RefCell::new(ClientWindows::default()));
+    add_client_click(&widget, &windows, worker);
+    add_client_drag(&widget, &windows);
+
+    ClientEntry {
+        widget,
+        badge,
+        icon: client.icons.first().cloned(),
+        windows,
+    }
+}
+
+fn create_client_icon(client: &HyprlandClient, config: &BarConfig) -> Widget {
+    if let Some(icon_path) = client.icons.first() {
+        let image = Image::from_file(icon_path);
+        image.set_pixel_size(config.icon_size);
+        image.set_margin_end(4);
+        image.upcast()
+    } else {
+        let fallback = Label::new(Some(&client.class));
+        fallback.set_margin_end(6);
+        fallback.set_opacity(0.8);
+        fallback.upcast()
+    }
+}
+
+/// Клик по иконке фокусирует окно (для группы — следующее по кругу),
+/// средняя кнопка закрывает окно
+fn add_client_click(widget: &Widget, windows: &Rc<RefCell<ClientWindows>>, worker: &Worker) {
+    let windows = windows.clone();
+    let worker = worker.clone();
+    let click = GestureClick::new();
+    click.set_button(0);
+    click.connect_released(move |gesture, _, _, _| {
+        // Клик по окну не должен переключать workspace, в котором оно находится.
+        // Последовательность захватывается только при отпускании: захват при нажатии
+        // отменил бы перетаскивание иконки (DragSource на том же виджете)
+        gesture.set_state(EventSequenceState::Claimed);
+        let windows = windows.borrow();
+        let action = match gesture.current_button() {
+            BUTTON_PRIMARY => windows.next().map(|address| DispatchType::FocusWindow(window(address))),
+            BUTTON_MIDDLE => windows.current().map(|address| DispatchType::CloseWindow(window(address))),
+            _ => None,
+        };
+        if let Some(action) = action {
+            dispatch(&worker, "WorkspaceClient", move || Dispatch::call(action));
+        }
+    });
+    widget.add_controller(click);
+}
+
+/// Иконку окна можно перетащить на другой workspace; данные перетаскивания — адрес окна
+fn add_client_drag(widget: &Widget, windows: &Rc<RefCell<ClientWindows>>) {
+    let windows = windows.clone();
+    let source = DragSource::new();
+    source.set_actions(DragAction::MOVE);
+    source.connect_prepare(move |_, _, _| {
+        let windows = windows.borrow();
+        let address = windows.current()?;
+        Some(ContentProvider::for_value(&address.to_value()))
+    });
+    source.connect_drag_begin(|source, _| {
+        if let Some(widget) = source.widget() {
+            source.set_icon(Some(&WidgetPaintable::new(Some(&widget))), 0, 0);
+        }
+    });
+    widget.add_controller(source);
+}
+
+/// Отправляет диспатч Hyprland в фоновом потоке: если Hyprland не отвечает,
+/// обработчик события GTK не должен блокировать бар
+fn dispatch(worker: &Worker, context: &'static str, task: impl FnOnce() -> hyprland::Result<()> + Send + 'static) {
+    let worker = worker.clone();
+    MainContext::default().spawn_local(async move {
+        match worker.run(task).await {
+            Some(Ok(())) => {}
+            Some(Err(err)) => logger::log_error(context, err),
+            None => logger::log_error(context, "Worker unavailable"),
+        }
+    });
+}
+
+fn window(address: &str) -> WindowIdentifier<'static> {
+    WindowIdentifier::Address(Address::new(address))
+}
+
+/// Расставляет дочерние виджеты контейнера в заданном порядке, не пересоздавая их.
+/// Виджеты, уже стоящие на своем месте, не трогаются.
+fn reorder_children(container: &Box, order: &[Widget]) {
+    let mut prev: Option<&Widget> = None;
+    for widget in order {
+        if widget.prev_sibling().as_ref() != prev {
+            container.reorder_child_after(widget, prev);
+        }
+        prev = Some(widget);
+    }
+}
@@ -0,0 +1,27 @@
+use gtk4::{Settings, gdk::{Display, Monitor}, prelude::*};
+
+use crate::config::BarConfig;
+
+/// Передает поиску иконок тему из конфигурации или настроек GTK,
+/// размер иконок и наибольший масштаб среди мониторов
+pub fn apply_icon_theme(config: &BarConfig) {
+    let theme = config
+        .icon_theme
+        .clone()
+        .or_else(|| Settings::default().and_then(|settings| settings.gtk_icon_theme_name()).map(String::from))
+        .unwrap_or_else(|| helpers::FALLBACK_THEME.to_string());
+    helpers::set_icon_theme(&theme, config.icon_size.unsigned_abs(), max_scale());
+}
+
+fn max_scale() -> u32 {
+    let Some(display) = Display::default() else {
+        return 1;
+    };
+    let monitors = display.monitors();
+    (0..monitors.n_items())
+        .filter_map(|i| monitors.item(i))
+        .filter_map(|obj| obj.downcast::<Monitor>().ok())
+        .map(|monitor| monitor.scale_factor().unsigned_abs())
+        .max()
+        .unwrap_or(1)
+}
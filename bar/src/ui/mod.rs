@@ -1,7 +1,9 @@
+pub mod icons;
 pub mod styles;
 pub mod window;
 pub mod components;
 
-pub use styles::load_css;
-pub use window::setup_window;
+pub use icons::apply_icon_theme;
+pub use styles::{css_path, load_css, reload_css};
+pub use window::{apply_window_config, setup_window};
 
@@ -1,6 +1,8 @@
 use gtk4::{CssProvider, gdk::Display};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::config::{BarConfig, config_dir};
 
 const DEFAULT_CSS: &str = r#"
 window {
@@ -12,6 +14,10 @@ window {
     padding: 6px 12px;
 }
 
+.bar.vertical {
+    padding: 12px 6px;
+}
+
 .workspaces {
     color: #e5e7eb;
 }
@@ -30,23 +36,103 @@ window {
     box-shadow: 0 0 0 1px rgba(125, 211, 252, 0.25);
 }
 
+.workspace.empty {
+    opacity: 0.5;
+}
+
+.workspace.special {
+    border-style: dashed;
+    opacity: 0.75;
+}
+
+.workspace.special.overlay {
+    opacity: 1;
+    border-color: rgba(196, 181, 253, 0.7);
+    background-color: rgba(196, 181, 253, 0.16);
+    box-shadow: 0 0 0 1px rgba(196, 181, 253, 0.25);
+}
+
+.workspace.urgent {
+    border-color: rgba(251, 146, 60, 0.9);
+    background-color: rgba(251, 146, 60, 0.2);
+}
+
+.workspace .client.focused {
+    border-bottom: 2px solid rgba(125, 211, 252, 0.9);
+}
+
 .workspace.hover {
     border-color: rgba(255, 255, 255, 0.4);
     background-color: rgba(255, 255, 255, 0.12);
     box-shadow: 0 0 0 1px rgba(255, 255, 255, 0.12);
 }
 
+.workspace:drop(active) {
+    border-color: rgba(134, 239, 172, 0.8);
+    background-color: rgba(134, 239, 172, 0.14);
+}
+
+.workspace:focus-visible {
+    outline: 1px solid rgba(125, 211, 252, 0.9);
+    outline-offset: 1px;
+}
+
+.workspace .client .count {
+    font-size: 9px;
+    font-weight: 700;
+    min-width: 12px;
+    padding: 0 2px;
+    border-radius: 6px;
+    color: #111827;
+    background-color: rgba(229, 231, 235, 0.9);
+}
+
+.workspace .more {
+    font-size: 11px;
+    opacity: 0.8;
+}
+
 .workspace .title {
     font-weight: 600;
     margin-right: 4px;
 }
 
+.bar.vertical .workspace .title {
+    margin-right: 0;
+}
+
+.workspace-tooltip .name {
+    font-weight: 600;
+}
+
 .lang {
     color: #e5e7eb;
     font-weight: 600;
     letter-spacing: 0.3px;
 }
 
+popover.lang-menu contents {
+    background-color: rgba(17, 24, 39, 0.8);
+    border: 1px solid rgba(255, 255, 255, 0.2);
+    border-radius: 8px;
+    padding: 4px;
+}
+
+popover.lang-menu button {
+    padding: 6px 12px;
+    border-radius: 4px;
+    color: #e5e7eb;
+}
+
+popover.lang-menu button:hover {
+    background-color: rgba(255, 255, 255, 0.1);
+}
+
+popover.lang-menu button.active {
+    font-weight: 600;
+    background-color: rgba(255, 255, 255, 0.15);
+}
+
 .clock {
     color: #e5e7eb;
     font-weight: 600;
@@ -57,6 +143,10 @@ window {
     color: #e5e7eb;
 }
 
+.module.error {
+    color: #fca5a5;
+}
+
 .tray-item-letter {
     color: #e5e7eb;
     font-weight: 600;
@@ -151,14 +241,52 @@ popover.tray-menu menuitem[action^="separator"] label {
 }
 "#;
 
-/// Загружает CSS стили из файла или использует встроенные стили по умолчанию
-pub fn load_css() {
+/// Стандартный путь к стилям в дереве исходников
+const RESOURCES_CSS: &str = "bar/resources/styles.css";
+/// Имя файла стилей в каталоге конфигурации
+const USER_CSS: &str = "style.css";
+
+/// Определяет путь к файлу стилей: `style` из конфигурации,
+/// `$XDG_CONFIG_HOME/oxidbar/style.css` или `bar/resources/styles.css`
+pub fn css_path(config: &BarConfig) -> Option<PathBuf> {
+    if let Some(style) = &config.style {
+        return Some(style.clone());
+    }
+
+    config_dir()
+        .map(|dir| dir.join(USER_CSS))
+        .into_iter()
+        .chain(std::iter::once(PathBuf::from(RESOURCES_CSS)))
+        .find(|path| path.exists())
+}
+
+/// Загружает CSS стили из файла или использует встроенные стили по умолчанию.
+///
+/// Возвращает зарегистрированный для дисплея провайдер, который затем
+/// можно перезагрузить на месте через [`reload_css`].
+pub fn load_css(path: Option<&Path>) -> CssProvider {
     let provider = CssProvider::new();
-    
-    // Пытаемся загрузить из файла
-    let css_path = Path::new("bar/resources/styles.css");
-    let css_content = if css_path.exists() {
-        match fs::read_to_string(css_path) {
+    provider.connect_parsing_error(|_, section, error| {
+        logger::log_warning("CSS", format!("{}: {}", section.to_str(), error));
+    });
+
+    reload_css(&provider, path);
+
+    if let Some(display) = Display::default() {
+        gtk4::style_context_add_provider_for_display(
+            &display,
+            &provider,
+            gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
+        );
+    }
+
+    provider
+}
+
+/// Перечитывает стили в уже зарегистрированный провайдер
+pub fn reload_css(provider: &CssProvider, path: Option<&Path>) {
+    let css_content = match path {
+        Some(css_path) if css_path.exists() => match fs::read_to_string(css_path) {
             Ok(content) => {
                 logger::log_info("Loaded CSS from file", css_path.display());
                 content
@@ -167,20 +295,12 @@ pub fn load_css() {
                 logger::log_error("Failed to read CSS file", e);
                 DEFAULT_CSS.to_string()
             }
+        },
+        _ => {
+            logger::log_error("Failed to read CSS file", "File not found");
+            DEFAULT_CSS.to_string()
         }
-    } else {
-        logger::log_error("Failed to read CSS file", "File not found");
-        DEFAULT_CSS.to_string()
     };
-    
-    provider.load_from_data(&css_content);
 
-    if let Some(display) = Display::default() {
-        gtk4::style_context_add_provider_for_display(
-            &display,
-            &provider,
-            gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
-        );
-    }
+    provider.load_from_data(&css_content);
 }
-
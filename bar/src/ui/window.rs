@@ -1,43 +1,76 @@
-use gtk4::{Align, ApplicationWindow, gdk::Display, gdk::Monitor, prelude::*};
-use gtk4_layer_shell::{Edge, Layer, LayerShell};
+use gtk4::{Align, ApplicationWindow, gdk::Monitor, prelude::*};
+use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
 
-use crate::config::BarConfig;
-
-/// Настраивает окно приложения для Wayland layer shell
-pub fn setup_window(window: &ApplicationWindow, config: &BarConfig) {
-    window.set_hexpand(true);
-    window.set_halign(Align::Fill);
-    window.set_default_height(config.height);
+use crate::config::{BarConfig, BarLayer, Position};
 
+/// Настраивает окно приложения для Wayland layer shell на указанном мониторе
+pub fn setup_window(window: &ApplicationWindow, config: &BarConfig, monitor: &Monitor) {
     window.init_layer_shell();
-    window.set_layer(Layer::Top);
+    window.set_monitor(Some(monitor));
     window.set_namespace(Some("oxidbar"));
-    window.auto_exclusive_zone_enable();
-    window.set_anchor(Edge::Top, true);
-    window.set_anchor(Edge::Left, true);
-    window.set_anchor(Edge::Right, true);
-    window.set_margin(Edge::Top, 0);
-    window.set_margin(Edge::Left, 0);
-    window.set_margin(Edge::Right, 0);
-    
-    set_full_width(window);
     window.set_decorated(false);
     window.set_resizable(false);
+
+    apply_window_config(window, config, monitor);
 }
 
-fn set_full_width(window: &ApplicationWindow) {
-    if let Some(display) = Display::default() {
-        let monitors = display.monitors();
-        if let Some(monitor) = monitors
-            .item(0)
-            .and_then(|obj| obj.downcast::<Monitor>().ok())
-        {
-            let geo = monitor.geometry();
-            window.set_default_width(geo.width());
-            window.set_size_request(geo.width(), -1);
-        }
+/// Применяет параметры конфигурации к уже созданному layer-shell окну
+pub fn apply_window_config(window: &ApplicationWindow, config: &BarConfig, monitor: &Monitor) {
+    window.set_layer(layer(config.layer));
+    window.auto_exclusive_zone_enable();
+    // С навигацией фокус клавиатуры переходит к бару по клику, иначе остается у окон
+    window.set_keyboard_mode(if config.keyboard_navigation { KeyboardMode::OnDemand } else { KeyboardMode::None });
+
+    // Бар прикреплен к своему краю и растянут вдоль него
+    let (edge, stretched) = match config.position {
+        Position::Top => (Edge::Top, [Edge::Left, Edge::Right]),
+        Position::Bottom => (Edge::Bottom, [Edge::Left, Edge::Right]),
+        Position::Left => (Edge::Left, [Edge::Top, Edge::Bottom]),
+        Position::Right => (Edge::Right, [Edge::Top, Edge::Bottom]),
+    };
+    for anchor in [Edge::Top, Edge::Bottom, Edge::Left, Edge::Right] {
+        window.set_anchor(anchor, anchor == edge || stretched.contains(&anchor));
     }
+
+    window.set_margin(Edge::Top, config.margin.top);
+    window.set_margin(Edge::Bottom, config.margin.bottom);
+    window.set_margin(Edge::Left, config.margin.left);
+    window.set_margin(Edge::Right, config.margin.right);
+
+    if config.position.is_vertical() {
+        set_full_height(window, config, monitor);
+    } else {
+        set_full_width(window, config, monitor);
+    }
+}
+
+fn layer(layer: BarLayer) -> Layer {
+    match layer {
+        BarLayer::Background => Layer::Background,
+        BarLayer::Bottom => Layer::Bottom,
+        BarLayer::Top => Layer::Top,
+        BarLayer::Overlay => Layer::Overlay,
+    }
+}
+
+fn set_full_width(window: &ApplicationWindow, config: &BarConfig, monitor: &Monitor) {
+    let geo = monitor.geometry();
+    let width = geo.width() - config.margin.left - config.margin.right;
+    window.set_default_size(width, config.height);
+    window.set_size_request(width, -1);
     window.set_hexpand(true);
     window.set_halign(Align::Fill);
+    window.set_vexpand(false);
+    window.set_valign(Align::Fill);
 }
 
+fn set_full_height(window: &ApplicationWindow, config: &BarConfig, monitor: &Monitor) {
+    let geo = monitor.geometry();
+    let height = geo.height() - config.margin.top - config.margin.bottom;
+    window.set_default_size(config.height, height);
+    window.set_size_request(-1, height);
+    window.set_vexpand(true);
+    window.set_valign(Align::Fill);
+    window.set_hexpand(false);
+    window.set_halign(Align::Fill);
+}
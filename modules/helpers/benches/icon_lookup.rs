@@ -0,0 +1,56 @@
+//! Время построения, загрузки индекса иконок и поиска по нему.
+//!
+//! Запуск: `cargo bench -p helpers`; тема берется из `OXIDBAR_ICON_THEME`
+//! (по умолчанию `hicolor`).
+
+use std::{env, hint::black_box, path::PathBuf, sync::Arc, time::Instant};
+
+use helpers::{IconIndex, IconLookup, icon_base_dirs};
+
+/// Имена, которые бар обычно ищет: иконки приложений, классы окон и несуществующие
+const NAMES: [&str; 8] = [
+    "firefox",
+    "kitty",
+    "org.gnome.Nautilus",
+    "code",
+    "steam",
+    "telegram",
+    "utilities-terminal",
+    "no-such-icon",
+];
+const ITERATIONS: u32 = 1000;
+
+fn main() {
+    let roots = icon_base_dirs();
+    let theme = env::var("OXIDBAR_ICON_THEME").unwrap_or_else(|_| helpers::FALLBACK_THEME.to_string());
+
+    let start = Instant::now();
+    let index = IconIndex::build(&roots);
+    println!("build: {} icons in {:?}", index.len(), start.elapsed());
+
+    let cache = env::temp_dir().join(format!("oxidbar-bench-{}.bin", std::process::id()));
+    let start = Instant::now();
+    index.save(&cache).expect("save icon index");
+    println!("save: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let loaded = IconIndex::load(&cache).expect("load icon index");
+    println!("load: {:?}", start.elapsed());
+    let start = Instant::now();
+    let fresh = loaded.is_fresh(&roots);
+    println!("freshness check: {fresh} in {:?}", start.elapsed());
+    let _ = std::fs::remove_file(&cache);
+
+    let start = Instant::now();
+    let lookup = IconLookup::new(Arc::new(loaded), &theme, 24, 1);
+    println!("theme chain {:?}: {:?}", lookup.theme_names(), start.elapsed());
+
+    for name in NAMES {
+        let start = Instant::now();
+        let mut found: Option<PathBuf> = None;
+        for _ in 0..ITERATIONS {
+            found = black_box(lookup.lookup(black_box(name)));
+        }
+        println!("lookup {name}: {:?}/iter -> {:?}", start.elapsed() / ITERATIONS, found);
+    }
+}
@@ -0,0 +1,333 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use anyhow::{Result, bail};
+
+use crate::xdg::home_dir;
+
+/// Заголовок файла индекса; версия меняется вместе с форматом
+const MAGIC: &[u8; 4] = b"OXIC";
+const VERSION: u32 = 1;
+
+/// Глубина обхода: `<base>/<theme>/<size>/<context>/<file>`
+const MAX_DEPTH: usize = 4;
+
+/// Расширения файлов иконок в порядке предпочтения: векторные четче при любом размере
+pub(crate) const EXTENSIONS: [&str; 3] = ["svg", "png", "xpm"];
+
+/// Файл иконки из индекса
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct IconFile {
+    /// Тема; `None` для иконок прямо в базовом каталоге (`pixmaps`)
+    pub theme: Option<String>,
+    /// Подкаталог темы, например `48x48/apps`
+    pub dir: String,
+    pub ext: String,
+    pub path: PathBuf,
+}
+
+/// Индекс файлов иконок во всех базовых каталогах: имя иконки → файлы.
+///
+/// Строится один раз и сохраняется на диск; устаревшим считается, если
+/// изменилось время модификации любого из просмотренных каталогов.
+#[derive(Debug, Default, PartialEq)]
+pub struct IconIndex {
+    roots: Vec<PathBuf>,
+    /// Все просмотренные каталоги и их mtime (0 — каталога не было)
+    dirs: Vec<(PathBuf, u64)>,
+    /// Пути файлов относительно базового каталога, по имени иконки
+    icons: HashMap<String, Vec<(usize, String)>>,
+}
+
+impl IconIndex {
+    /// Обходит базовые каталоги и собирает все файлы иконок
+    pub fn build(roots: &[PathBuf]) -> Self {
+        let mut index = Self {
+            roots: roots.to_vec(),
+            ..Self::default()
+        };
+        for (root_index, root) in roots.iter().enumerate() {
+            index.scan(root_index, root, "", 0);
+        }
+        index
+    }
+
+    fn scan(&mut self, root_index: usize, dir: &Path, relative: &str, depth: usize) {
+        self.dirs.push((dir.to_path_buf(), mtime(dir)));
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+        paths.sort();
+        for path in paths {
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let child = if relative.is_empty() {
+                file_name.to_string()
+            } else {
+                format!("{relative}/{file_name}")
+            };
+            if path.is_dir() {
+                if depth < MAX_DEPTH {
+                    self.scan(root_index, &path, &child, depth + 1);
+                }
+            } else if let Some((name, ext)) = file_name.rsplit_once('.')
+                && EXTENSIONS.contains(&ext)
+            {
+                self.icons.entry(name.to_string()).or_default().push((root_index, child));
+            }
+        }
+    }
+
+    /// Базовые каталоги, из которых построен индекс
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Количество имен иконок
+    pub fn len(&self) -> usize {
+        self.icons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.icons.is_empty()
+    }
+
+    /// Актуален ли индекс для этих базовых каталогов
+    pub fn is_fresh(&self, roots: &[PathBuf]) -> bool {
+        self.roots == roots && self.dirs.iter().all(|(dir, modified)| mtime(dir) == *modified)
+    }
+
+    /// Файлы иконки в порядке базовых каталогов
+    pub(crate) fn files(&self, name: &str) -> Vec<IconFile> {
+        let Some(entries) = self.icons.get(name) else {
+            return Vec::new();
+        };
+        entries
+            .iter()
+            .filter_map(|(root, relative)| {
+                let (parent, file_name) = relative.rsplit_once('/').unwrap_or(("", relative));
+                let (_, ext) = file_name.rsplit_once('.')?;
+                let (theme, dir) = match parent.split_once('/') {
+                    Some((theme, dir)) => (Some(theme.to_string()), dir.to_string()),
+                    None if parent.is_empty() => (None, String::new()),
+                    // Файл прямо в корне темы не относится ни к одному каталогу размеров
+                    None => return None,
+                };
+                Some(IconFile {
+                    theme,
+                    dir,
+                    ext: ext.to_string(),
+                    path: self.roots.get(*root)?.join(relative),
+                })
+            })
+            .collect()
+    }
+
+    /// Загружает индекс из файла
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut reader = io::BufReader::new(fs::File::open(path)?);
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_u32(&mut reader)? != VERSION {
+            bail!("Unsupported icon index format");
+        }
+
+        let roots = (0..read_u32(&mut reader)?)
+            .map(|_| read_string(&mut reader).map(PathBuf::from))
+            .collect::<Result<_>>()?;
+        let dirs = (0..read_u32(&mut reader)?)
+            .map(|_| Ok((PathBuf::from(read_string(&mut reader)?), read_u64(&mut reader)?)))
+            .collect::<Result<_>>()?;
+        let mut icons = HashMap::new();
+        for _ in 0..read_u32(&mut reader)? {
+            let name = read_string(&mut reader)?;
+            let files = (0..read_u32(&mut reader)?)
+                .map(|_| Ok((read_u32(&mut reader)? as usize, read_string(&mut reader)?)))
+                .collect::<Result<_>>()?;
+            icons.insert(name, files);
+        }
+        Ok(Self { roots, dirs, icons })
+    }
+
+    /// Сохраняет индекс в файл, создавая каталог при необходимости
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Запись во временный файл и rename, чтобы не оставить обрезанный индекс
+        let tmp = path.with_extension("tmp");
+        let mut writer = io::BufWriter::new(fs::File::create(&tmp)?);
+        writer.write_all(MAGIC)?;
+        write_u32(&mut writer, VERSION)?;
+
+        write_u32(&mut writer, self.roots.len())?;
+        for root in &self.roots {
+            write_string(&mut writer, &root.to_string_lossy())?;
+        }
+        write_u32(&mut writer, self.dirs.len())?;
+        for (dir, modified) in &self.dirs {
+            write_string(&mut writer, &dir.to_string_lossy())?;
+            writer.write_all(&modified.to_le_bytes())?;
+        }
+        write_u32(&mut writer, self.icons.len())?;
+        for (name, files) in &self.icons {
+            write_string(&mut writer, name)?;
+            write_u32(&mut writer, files.len())?;
+            for (root, relative) in files {
+                write_u32(&mut writer, *root)?;
+                write_string(&mut writer, relative)?;
+            }
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Загружает индекс из кэша, если он актуален, иначе строит заново и сохраняет
+    pub fn load_or_build(cache: &Path, roots: &[PathBuf]) -> Self {
+        match Self::load(cache) {
+            Ok(index) if index.is_fresh(roots) => {
+                logger::log_info("IconIndex", format!("Loaded {} icons from {}", index.len(), cache.display()));
+                return index;
+            }
+            Ok(_) => logger::log_info("IconIndex", "Icon index is stale, rebuilding"),
+            Err(e) if e.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::NotFound) => {}
+            Err(e) => logger::log_error("IconIndex", format!("{}: {e}", cache.display())),
+        }
+
+        let index = Self::build(roots);
+        logger::log_info("IconIndex", format!("Indexed {} icons", index.len()));
+        if let Err(e) = index.save(cache) {
+            logger::log_error("IconIndex", format!("Failed to save {}: {e}", cache.display()));
+        }
+        index
+    }
+}
+
+/// Путь к сохраненному индексу: `$XDG_CACHE_HOME/oxidbar/icons.bin`
+pub fn icon_index_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| home_dir().map(|home| home.join(".cache")))?;
+    Some(base.join("oxidbar").join("icons.bin"))
+}
+
+/// Время модификации в наносекундах; 0, если каталога нет
+fn mtime(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |time| time.as_nanos() as u64)
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_string(reader: &mut impl Read) -> Result<String> {
+    let mut buf = vec![0u8; read_u32(reader)? as usize];
+    reader.read_exact(&mut buf)?;
+    Ok(String::from_utf8(buf)?)
+}
+
+fn write_u32(writer: &mut impl Write, value: impl TryInto<u32>) -> Result<()> {
+    let Ok(value) = value.try_into() else {
+        bail!("Value does not fit into u32");
+    };
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_string(writer: &mut impl Write, value: &str) -> Result<()> {
+    write_u32(writer, value.len())?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+
+    #[test]
+    fn indexes_themed_and_unthemed_icons() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().to_path_buf();
+        touch(&dir.join("hicolor/48x48/apps/firefox.png"));
+        touch(&dir.join("hicolor/scalable/apps/firefox.svg"));
+        touch(&dir.join("xterm.xpm"));
+        touch(&dir.join("hicolor/index.theme"));
+        touch(&dir.join("hicolor/48x48/apps/readme.txt"));
+        let index = IconIndex::build(std::slice::from_ref(&dir));
+
+        assert_eq!(index.len(), 2);
+        let files = index.files("firefox");
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].theme.as_deref(), Some("hicolor"));
+        assert_eq!(files[0].dir, "48x48/apps");
+        assert_eq!(files[0].ext, "png");
+        assert_eq!(files[1].path, dir.join("hicolor/scalable/apps/firefox.svg"));
+        let xterm = index.files("xterm");
+        assert_eq!((xterm[0].theme.clone(), xterm[0].dir.as_str()), (None, ""));
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().to_path_buf();
+        touch(&dir.join("icons/hicolor/16x16/apps/kitty.png"));
+        let roots = [dir.join("icons"), dir.join("missing")];
+        let index = IconIndex::build(&roots);
+        let cache = dir.join("cache/icons.bin");
+        index.save(&cache).unwrap();
+
+        let loaded = IconIndex::load(&cache).unwrap();
+        assert_eq!(loaded, index);
+        assert!(loaded.is_fresh(&roots));
+        assert!(!loaded.is_fresh(&roots[..1]));
+
+        fs::write(&cache, b"garbage").unwrap();
+        assert!(IconIndex::load(&cache).is_err());
+    }
+
+    #[test]
+    fn becomes_stale_when_icons_are_installed() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().to_path_buf();
+        touch(&dir.join("hicolor/48x48/apps/kitty.png"));
+        let roots = [dir.clone()];
+        let index = IconIndex::build(&roots);
+        assert!(index.is_fresh(&roots));
+
+        // Новый файл меняет mtime каталога, в который он установлен
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        touch(&dir.join("hicolor/48x48/apps/steam.png"));
+        assert!(!index.is_fresh(&roots));
+
+        let cache = dir.join("icons.bin");
+        assert_eq!(IconIndex::load_or_build(&cache, &roots).files("steam").len(), 1);
+    }
+}
@@ -0,0 +1,385 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use ini::Ini;
+
+use crate::{
+    icon_index::{EXTENSIONS, IconFile, IconIndex},
+    xdg::{data_dirs, home_dir},
+};
+
+/// Тема, которой заканчивается любая цепочка наследования
+pub const FALLBACK_THEME: &str = "hicolor";
+
+/// Тип каталога темы (ключ `Type` в `index.theme`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DirType {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+/// Подкаталог темы с иконками одного размера
+#[derive(Debug, Clone)]
+struct ThemeDir {
+    path: String,
+    size: u32,
+    scale: u32,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+    kind: DirType,
+}
+
+impl ThemeDir {
+    fn parse(path: &str, content: &Ini) -> Option<Self> {
+        let section = content.section(Some(path))?;
+        let number = |key: &str| section.get(key).and_then(|value| value.trim().parse::<u32>().ok());
+        let size = number("Size")?;
+        let kind = match section.get("Type").map(str::trim) {
+            Some("Fixed") => DirType::Fixed,
+            Some("Scalable") => DirType::Scalable,
+            _ => DirType::Threshold,
+        };
+        Some(Self {
+            path: path.to_string(),
+            size,
+            scale: number("Scale").unwrap_or(1),
+            min_size: number("MinSize").unwrap_or(size),
+            max_size: number("MaxSize").unwrap_or(size),
+            threshold: number("Threshold").unwrap_or(2),
+            kind,
+        })
+    }
+
+    /// `DirectoryMatchesSize` из спецификации
+    fn matches(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.kind {
+            DirType::Fixed => self.size == size,
+            DirType::Scalable => (self.min_size..=self.max_size).contains(&size),
+            DirType::Threshold => self.size.abs_diff(size) <= self.threshold,
+        }
+    }
+
+    /// `DirectorySizeDistance` из спецификации
+    fn distance(&self, size: u32, scale: u32) -> u32 {
+        let wanted = size * scale;
+        let (min, max) = match self.kind {
+            DirType::Fixed => (self.size, self.size),
+            DirType::Scalable => (self.min_size, self.max_size),
+            DirType::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+        if wanted < min * self.scale {
+            min * self.scale - wanted
+        } else {
+            wanted.saturating_sub(max * self.scale)
+        }
+    }
+}
+
+/// Тема иконок: каталоги с размерами и родительские темы из `index.theme`
+#[derive(Debug, Clone)]
+struct IconTheme {
+    name: String,
+    dirs: Vec<ThemeDir>,
+    inherits: Vec<String>,
+}
+
+impl IconTheme {
+    /// Загружает `index.theme` из первого базового каталога, где тема установлена
+    fn load(name: &str, base_dirs: &[PathBuf]) -> Option<Self> {
+        let index = base_dirs
+            .iter()
+            .map(|base| base.join(name).join("index.theme"))
+            .find(|path| path.is_file())?;
+        let content = match Ini::load_from_file_noescape(&index) {
+            Ok(content) => content,
+            Err(e) => {
+                logger::log_error("IconTheme::load", format!("{}: {e}", index.display()));
+                return None;
+            }
+        };
+        let section = content.section(Some("Icon Theme"))?;
+        let list = |key: &str| -> Vec<String> {
+            section
+                .get(key)
+                .unwrap_or("")
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(String::from)
+                .collect()
+        };
+
+        let mut paths = list("Directories");
+        paths.extend(list("ScaledDirectories"));
+        let mut seen = HashSet::new();
+        paths.retain(|path| seen.insert(path.clone()));
+
+        Some(Self {
+            name: name.to_string(),
+            dirs: paths.iter().filter_map(|path| ThemeDir::parse(path, &content)).collect(),
+            inherits: list("Inherits"),
+        })
+    }
+}
+
+/// Поиск иконок по Icon Theme Specification для заданной темы, размера и масштаба.
+///
+/// Файлы берутся из [`IconIndex`], поэтому поиск не обращается к диску.
+#[derive(Debug, Clone)]
+pub struct IconLookup {
+    index: Arc<IconIndex>,
+    /// Тема и все ее предки в порядке поиска; `hicolor` последней
+    themes: Vec<IconTheme>,
+    size: u32,
+    scale: u32,
+}
+
+impl IconLookup {
+    /// Загружает тему и цепочку `Inherits` из базовых каталогов индекса
+    pub fn new(index: Arc<IconIndex>, theme: &str, size: u32, scale: u32) -> Self {
+        let mut themes = Vec::new();
+        let mut visited = HashSet::new();
+        load_theme_chain(theme, index.roots(), &mut visited, &mut themes);
+        if !visited.contains(FALLBACK_THEME) {
+            load_theme_chain(FALLBACK_THEME, index.roots(), &mut visited, &mut themes);
+        }
+        Self {
+            index,
+            themes,
+            size: size.max(1),
+            scale: scale.max(1),
+        }
+    }
+
+    /// Индекс, по которому ведется поиск
+    pub fn index(&self) -> &Arc<IconIndex> {
+        &self.index
+    }
+
+    /// Имена тем в порядке поиска
+    pub fn theme_names(&self) -> Vec<&str> {
+        self.themes.iter().map(|theme| theme.name.as_str()).collect()
+    }
+
+    /// Путь к иконке: точное совпадение размера в теме, затем ближайший размер
+    /// в ней же, затем родительские темы и, наконец, иконки вне тем (`pixmaps`)
+    pub fn lookup(&self, name: &str) -> Option<PathBuf> {
+        if name.is_empty() {
+            return None;
+        }
+        let path = Path::new(name);
+        if path.is_absolute() {
+            return path.is_file().then(|| path.to_path_buf());
+        }
+        let files = self.index.files(name);
+        if files.is_empty() {
+            return None;
+        }
+        self.themes
+            .iter()
+            .find_map(|theme| self.lookup_in_theme(theme, &files))
+            .or_else(|| lookup_unthemed(&files))
+    }
+
+    fn lookup_in_theme(&self, theme: &IconTheme, files: &[IconFile]) -> Option<PathBuf> {
+        let files: Vec<&IconFile> = files
+            .iter()
+            .filter(|file| file.theme.as_deref() == Some(theme.name.as_str()))
+            .collect();
+        let find = |dir: &ThemeDir, ext: &str| {
+            files
+                .iter()
+                .find(|file| file.dir == dir.path && file.ext == ext)
+                .map(|file| file.path.clone())
+        };
+
+        // Точное совпадение; векторные файлы предпочтительнее растровых
+        for ext in EXTENSIONS {
+            for dir in theme.dirs.iter().filter(|dir| dir.matches(self.size, self.scale)) {
+                if let Some(path) = find(dir, ext) {
+                    return Some(path);
+                }
+            }
+        }
+
+        // Ближайший размер; при равном расстоянии — масштабируемый каталог и SVG
+        let mut best: Option<((u32, bool, usize), PathBuf)> = None;
+        for dir in &theme.dirs {
+            let distance = dir.distance(self.size, self.scale);
+            let not_scalable = dir.kind != DirType::Scalable;
+            for (rank, ext) in EXTENSIONS.iter().enumerate() {
+                let key = (distance, not_scalable, rank);
+                if best.as_ref().is_some_and(|(best_key, _)| *best_key <= key) {
+                    continue;
+                }
+                if let Some(path) = find(dir, ext) {
+                    best = Some((key, path));
+                }
+            }
+        }
+        best.map(|(_, path)| path)
+    }
+}
+
+fn lookup_unthemed(files: &[IconFile]) -> Option<PathBuf> {
+    EXTENSIONS.iter().find_map(|ext| {
+        files
+            .iter()
+            .find(|file| file.theme.is_none() && file.ext == *ext)
+            .map(|file| file.path.clone())
+    })
+}
+
+fn load_theme_chain(name: &str, base_dirs: &[PathBuf], visited: &mut HashSet<String>, themes: &mut Vec<IconTheme>) {
+    if !visited.insert(name.to_string()) {
+        return;
+    }
+    let Some(theme) = IconTheme::load(name, base_dirs) else {
+        logger::log_info("IconLookup", format!("Icon theme '{name}' not found"));
+        return;
+    };
+    let inherits = theme.inherits.clone();
+    themes.push(theme);
+    for parent in inherits {
+        // hicolor всегда ищется последним, даже если тема указала его явно
+        if parent != FALLBACK_THEME {
+            load_theme_chain(&parent, base_dirs, visited, themes);
+        }
+    }
+}
+
+/// Базовые каталоги иконок: `~/.icons`, `$XDG_DATA_DIRS/icons` (включая
+/// `~/.local/share/icons`) и `/usr/share/pixmaps`
+pub fn base_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = home_dir().map(|home| home.join(".icons")).into_iter().collect();
+    dirs.extend(data_dirs().into_iter().map(|dir| dir.join("icons")));
+    dirs.push(PathBuf::from("/usr/share/pixmaps"));
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Каталог иконок с темами во временном каталоге
+    struct IconsDir(TempDir);
+
+    impl IconsDir {
+        fn new() -> Self {
+            Self(TempDir::new().unwrap())
+        }
+
+        fn root(&self) -> &Path {
+            self.0.path()
+        }
+
+        fn theme(&self, name: &str, inherits: &str, dirs: &[(&str, &str)]) {
+            let mut index = format!(
+                "[Icon Theme]\nName={name}\nInherits={inherits}\nDirectories={}\n",
+                dirs.iter().map(|(path, _)| *path).collect::<Vec<_>>().join(",")
+            );
+            for (path, section) in dirs {
+                index.push_str(&format!("\n[{path}]\n{section}\n"));
+            }
+            fs::create_dir_all(self.root().join(name)).unwrap();
+            fs::write(self.root().join(name).join("index.theme"), index).unwrap();
+        }
+
+        fn icon(&self, relative: &str) -> PathBuf {
+            let path = self.root().join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "").unwrap();
+            path
+        }
+
+        fn lookup(&self, theme: &str, size: u32, scale: u32) -> IconLookup {
+            IconLookup::new(Arc::new(IconIndex::build(&[self.root().to_path_buf()])), theme, size, scale)
+        }
+    }
+
+    const FIXED_16: (&str, &str) = ("16x16/apps", "Size=16\nType=Fixed");
+    const FIXED_48: (&str, &str) = ("48x48/apps", "Size=48\nType=Fixed");
+    const SCALABLE: (&str, &str) = ("scalable/apps", "Size=48\nType=Scalable\nMinSize=8\nMaxSize=512");
+
+    #[test]
+    fn follows_inherits_down_to_hicolor() {
+        let dir = IconsDir::new();
+        dir.theme("Papirus-Dark", "Papirus", &[FIXED_16]);
+        dir.theme("Papirus", "breeze,hicolor", &[FIXED_48]);
+        dir.theme("breeze", "", &[FIXED_48]);
+        dir.theme("hicolor", "", &[FIXED_48]);
+        let hicolor = dir.icon("hicolor/48x48/apps/steam.png");
+        let papirus = dir.icon("Papirus/48x48/apps/firefox.svg");
+        dir.icon("hicolor/48x48/apps/firefox.png");
+        let lookup = dir.lookup("Papirus-Dark", 48, 1);
+        assert_eq!(lookup.theme_names(), ["Papirus-Dark", "Papirus", "breeze", "hicolor"]);
+        assert_eq!(lookup.lookup("firefox"), Some(papirus));
+        assert_eq!(lookup.lookup("steam"), Some(hicolor));
+        assert_eq!(lookup.lookup("missing"), None);
+    }
+
+    #[test]
+    fn prefers_exact_size_then_closest() {
+        let dir = IconsDir::new();
+        dir.theme("hicolor", "", &[FIXED_16, FIXED_48, ("32x32/apps", "Size=32\nType=Fixed")]);
+        let exact = dir.icon("hicolor/16x16/apps/kitty.png");
+        let closest = dir.icon("hicolor/32x32/apps/kitty.png");
+        dir.icon("hicolor/48x48/apps/kitty.png");
+
+        assert_eq!(dir.lookup("hicolor", 16, 1).lookup("kitty"), Some(exact));
+        assert_eq!(dir.lookup("hicolor", 28, 1).lookup("kitty"), Some(closest));
+    }
+
+    #[test]
+    fn prefers_scalable_icons() {
+        let dir = IconsDir::new();
+        dir.theme("hicolor", "", &[FIXED_16, SCALABLE]);
+        dir.icon("hicolor/16x16/apps/code.png");
+        let svg = dir.icon("hicolor/scalable/apps/code.svg");
+
+        assert_eq!(dir.lookup("hicolor", 24, 1).lookup("code"), Some(svg.clone()));
+        // Масштабируемый каталог покрывает и 16px, а SVG предпочтительнее PNG
+        assert_eq!(dir.lookup("hicolor", 16, 1).lookup("code"), Some(svg));
+    }
+
+    #[test]
+    fn matches_scale() {
+        let dir = IconsDir::new();
+        dir.theme(
+            "hicolor",
+            "",
+            &[("24x24/apps", "Size=24\nType=Fixed"), ("24x24@2/apps", "Size=24\nScale=2\nType=Fixed")],
+        );
+        let normal = dir.icon("hicolor/24x24/apps/mpv.png");
+        let hidpi = dir.icon("hicolor/24x24@2/apps/mpv.png");
+
+        assert_eq!(dir.lookup("hicolor", 24, 1).lookup("mpv"), Some(normal));
+        assert_eq!(dir.lookup("hicolor", 24, 2).lookup("mpv"), Some(hidpi));
+    }
+
+    #[test]
+    fn falls_back_to_unthemed_icons_and_paths() {
+        let dir = IconsDir::new();
+        dir.theme("hicolor", "", &[FIXED_48]);
+        let pixmap = dir.icon("xterm.xpm");
+        let lookup = dir.lookup("missing-theme", 48, 1);
+
+        assert_eq!(lookup.theme_names(), ["hicolor"]);
+        assert_eq!(lookup.lookup("xterm"), Some(pixmap.clone()));
+        assert_eq!(lookup.lookup(pixmap.to_str().unwrap()), Some(pixmap));
+        assert_eq!(lookup.lookup("/nonexistent/icon.png"), None);
+    }
+}
@@ -1,53 +1,138 @@
-use std::{collections::HashMap, fs, sync::Mutex};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+    thread,
+};
 
 use anyhow::Result;
 use once_cell::sync::Lazy;
 
-const HICOLOR: &str = "/usr/share/icons";
-const PIXMAPS: &str = "/usr/share/pixmaps";
+mod icon_index;
+mod icons;
+mod xdg;
 
-static ICON_CACHE: Lazy<Mutex<HashMap<String, Vec<String>>>> =
-    Lazy::new(|| Mutex::new(HashMap::new()));
+pub use icon_index::{IconIndex, icon_index_path};
+pub use icons::{FALLBACK_THEME, IconLookup, base_dirs as icon_base_dirs};
+pub use xdg::{data_dirs, home_dir};
 
-/// Получает пути к иконкам по имени иконки или классу приложения
-pub fn icon_fetcher(app_class_name: &str) -> Result<Vec<String>> {
-    if let Some(cached) = ICON_CACHE
-        .lock()
-        .ok()
-        .and_then(|cache| cache.get(app_class_name).cloned())
-    {
-        return Ok(cached);
-    }
+/// Размер иконок по умолчанию, пока бар не передал свои настройки
+const DEFAULT_ICON_SIZE: u32 = 48;
+
+/// Текущая тема иконок и поиск по ней
+struct IconState {
+    lookup: Option<IconLookup>,
+    theme: String,
+    size: u32,
+    scale: u32,
+}
 
-    let mut result: Vec<String> = vec![];
-    walker(HICOLOR, app_class_name, &mut result)?;
-    walker(PIXMAPS, app_class_name, &mut result)?;
+static ICON_STATE: Lazy<Mutex<IconState>> = Lazy::new(|| {
+    Mutex::new(IconState {
+        lookup: None,
+        theme: FALLBACK_THEME.to_string(),
+        size: DEFAULT_ICON_SIZE,
+        scale: 1,
+    })
+});
 
-    if let Ok(mut cache) = ICON_CACHE.lock() {
-        cache.insert(app_class_name.to_string(), result.clone());
+/// Индекс иконок; при первом обращении загружается из кэша или строится
+static ICON_INDEX: Lazy<RwLock<Arc<IconIndex>>> = Lazy::new(|| RwLock::new(Arc::new(load_icon_index())));
+
+fn load_icon_index() -> IconIndex {
+    let roots = icon_base_dirs();
+    match icon_index_path() {
+        Some(cache) => IconIndex::load_or_build(&cache, &roots),
+        None => IconIndex::build(&roots),
     }
+}
+
+fn current_index() -> Arc<IconIndex> {
+    match ICON_INDEX.read() {
+        Ok(index) => index.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
 
-    Ok(result)
+/// Загружает или строит индекс иконок в фоновом потоке, чтобы первый
+/// [`icon_fetcher`] не ждал обхода каталогов
+pub fn start_icon_index() {
+    let spawned = thread::Builder::new()
+        .name("oxidbar-icon-index".to_string())
+        .spawn(|| {
+            Lazy::force(&ICON_INDEX);
+        });
+    if let Err(e) = spawned {
+        logger::log_error("helpers::start_icon_index", e);
+    }
 }
 
-fn walker(path: &str, filename: &str, result: &mut Vec<String>) -> Result<()> {
-    let entries = fs::read_dir(path)?;
+/// Перестраивает индекс, если каталоги иконок изменились.
+///
+/// Возвращает `true`, если индекс был перестроен. Блокирует поток на время обхода.
+pub fn refresh_icon_index() -> bool {
+    let roots = icon_base_dirs();
+    if current_index().is_fresh(&roots) {
+        return false;
+    }
+    let index = Arc::new(match icon_index_path() {
+        Some(cache) => IconIndex::load_or_build(&cache, &roots),
+        None => IconIndex::build(&roots),
+    });
+    // Поиск по старому индексу пересоздается при следующем icon_fetcher
+    if let Ok(mut current) = ICON_INDEX.write() {
+        *current = index;
+    }
+    true
+}
 
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_dir() {
-            walker(path.to_str().unwrap(), filename, result)?;
-        } else if let Some(name) = path.file_stem()
-            && name == filename
-        {
-            let path = path.to_str();
-            if let Some(path) = path {
-                result.push(path.to_string());
-            }
+/// Каталоги, изменения в которых означают установку или удаление иконок:
+/// базовые каталоги и корни тем (туда пишется `icon-theme.cache`)
+pub fn icon_watch_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for base in icon_base_dirs() {
+        if let Ok(entries) = fs::read_dir(&base) {
+            dirs.extend(entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).filter(|path| path.is_dir()));
         }
+        dirs.push(base);
     }
+    dirs
+}
 
-    Ok(())
+/// Задает тему, размер и масштаб для [`icon_fetcher`]
+pub fn set_icon_theme(theme: &str, size: u32, scale: u32) {
+    let Ok(mut state) = ICON_STATE.lock() else {
+        return;
+    };
+    if state.theme == theme && state.size == size && state.scale == scale {
+        return;
+    }
+    logger::log_info("helpers::set_icon_theme", format!("Icon theme '{theme}', size {size}@{scale}"));
+    state.theme = theme.to_string();
+    state.size = size;
+    state.scale = scale;
+    state.lookup = None;
 }
 
+/// Получает пути к иконкам по имени иконки или классу приложения.
+///
+/// Иконка ищется в текущей теме по Icon Theme Specification; первым идет
+/// лучший вариант для заданного размера. Пустой список, если иконки нет.
+pub fn icon_fetcher(app_class_name: &str) -> Result<Vec<String>> {
+    // Индекс берется до блокировки состояния: его построение может занять время
+    let index = current_index();
+    let mut state = ICON_STATE
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Icon state lock poisoned"))?;
+    if state.lookup.as_ref().is_none_or(|lookup| !Arc::ptr_eq(lookup.index(), &index)) {
+        state.lookup = Some(IconLookup::new(index, &state.theme, state.size, state.scale));
+    }
+    let Some(lookup) = &state.lookup else {
+        return Ok(Vec::new());
+    };
+    Ok(lookup
+        .lookup(app_class_name)
+        .and_then(|path| path.to_str().map(String::from))
+        .into_iter()
+        .collect())
+}
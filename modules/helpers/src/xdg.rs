@@ -0,0 +1,36 @@
+use std::{collections::HashSet, env, path::PathBuf};
+
+/// Домашний каталог пользователя
+pub fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(PathBuf::from).filter(|p| p.is_absolute())
+}
+
+/// Data dirs в порядке приоритета: `$XDG_DATA_HOME`, `$XDG_DATA_DIRS`,
+/// затем экспорты Flatpak и профили Nix, если их нет в `$XDG_DATA_DIRS`
+pub fn data_dirs() -> Vec<PathBuf> {
+    let home = home_dir();
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| home.as_ref().map(|home| home.join(".local/share")));
+    let xdg_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    let mut dirs: Vec<PathBuf> = data_home.into_iter().collect();
+    dirs.extend(env::split_paths(&xdg_dirs).filter(|p| p.is_absolute()));
+    if let Some(home) = &home {
+        dirs.push(home.join(".local/share/flatpak/exports/share"));
+        dirs.push(home.join(".nix-profile/share"));
+    }
+    dirs.push(PathBuf::from("/var/lib/flatpak/exports/share"));
+    if let Ok(user) = env::var("USER") {
+        dirs.push(PathBuf::from(format!("/etc/profiles/per-user/{user}/share")));
+    }
+    dirs.push(PathBuf::from("/run/current-system/sw/share"));
+
+    let mut seen = HashSet::new();
+    dirs.retain(|dir| seen.insert(dir.clone()));
+    dirs
+}
@@ -0,0 +1,351 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use derive_more::Display as DeriveDisplay;
+use helpers::data_dirs;
+use ini::Ini;
+use once_cell::sync::Lazy;
+
+const DESKTOP_ENTRY: &str = "Desktop Entry";
+
+/// Индекс desktop-файлов, построенный один раз при первом обращении
+static DESKTOP_ENTRIES: Lazy<DesktopEntries> = Lazy::new(DesktopEntries::scan);
+
+/// Классы окон без desktop-файла: промах логируется один раз на класс
+static MISSING_CLASSES: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Debug, DeriveDisplay, Clone, PartialEq)]
+#[display("DesktopFile {{ id: {id}, name: {name}, exec: {exec}, icon: {icon:?} }}")]
+pub struct DesktopFile {
+    /// Desktop file ID, например `org.gnome.Nautilus`
+    pub id: String,
+    /// Имя приложения с учетом текущей локали
+    pub name: String,
+    pub exec: String,
+    pub icon: Option<String>,
+    /// Класс окна, который задает приложение (`StartupWMClass`)
+    pub startup_wm_class: Option<String>,
+    /// Приложение не показывается в меню (`NoDisplay`)
+    pub no_display: bool,
+}
+
+impl DesktopFile {
+    /// Ищет desktop-файл для класса окна в общем индексе
+    pub fn load(app_class_name: &str) -> Option<Self> {
+        let desktop = DESKTOP_ENTRIES.find(app_class_name).cloned();
+        if desktop.is_none()
+            && let Ok(mut missing) = MISSING_CLASSES.lock()
+            && missing.insert(app_class_name.to_string())
+        {
+            logger::log_info("DesktopFile::load", format!("Desktop file for '{}' not found", app_class_name));
+        }
+        desktop
+    }
+
+    /// Разбирает секцию `[Desktop Entry]`; `None` для скрытых записей и не-приложений
+    fn parse(id: String, content: &Ini, locale: Option<&str>) -> Option<Self> {
+        let section = content.section(Some(DESKTOP_ENTRY))?;
+        let flag = |key: &str| section.get(key).is_some_and(|value| value.trim() == "true");
+        if flag("Hidden") || section.get("Type").is_some_and(|kind| kind.trim() != "Application") {
+            return None;
+        }
+
+        let name = locale
+            .map(locale_keys)
+            .unwrap_or_default()
+            .iter()
+            .find_map(|key| section.get(format!("Name[{key}]")))
+            .or_else(|| section.get("Name"))?
+            .to_string();
+        let non_empty = |key: &str| section.get(key).map(str::trim).filter(|value| !value.is_empty()).map(String::from);
+
+        Some(Self {
+            id,
+            name,
+            exec: section.get("Exec").unwrap_or("").to_string(),
+            icon: non_empty("Icon"),
+            startup_wm_class: non_empty("StartupWMClass"),
+            no_display: flag("NoDisplay"),
+        })
+    }
+}
+
+/// Индекс desktop-файлов из всех XDG data dirs.
+///
+/// Окно сопоставляется с записью по `StartupWMClass`, desktop file ID,
+/// последнему компоненту reverse-DNS ID (`org.gnome.Nautilus` → `nautilus`)
+/// и имени приложения; все сравнения без учета регистра. Записи с `NoDisplay`
+/// используются, только если нет видимой записи с тем же ключом.
+#[derive(Debug, Default)]
+pub struct DesktopEntries {
+    entries: Vec<DesktopFile>,
+    by_wm_class: HashMap<String, usize>,
+    by_id: HashMap<String, usize>,
+    by_short_id: HashMap<String, usize>,
+    by_name: HashMap<String, usize>,
+}
+
+impl DesktopEntries {
+    /// Сканирует каталоги `applications` во всех data dirs с учетом текущей локали
+    pub fn scan() -> Self {
+        let dirs = data_dirs();
+        let locale = current_locale();
+        let entries = Self::from_dirs(&dirs, locale.as_deref());
+        logger::log_info(
+            "DesktopEntries::scan",
+            format!("Indexed {} desktop entries from {} data dirs", entries.entries.len(), dirs.len()),
+        );
+        entries
+    }
+
+    /// Строит индекс из data dirs в порядке приоритета: при совпадении ID
+    /// используется файл из первого каталога
+    pub fn from_dirs(data_dirs: &[PathBuf], locale: Option<&str>) -> Self {
+        let mut index = Self::default();
+        let mut seen = HashSet::new();
+        for data_dir in data_dirs {
+            let applications = data_dir.join("applications");
+            let mut files = Vec::new();
+            collect_desktop_files(&applications, &applications, &mut files);
+            for (id, path) in files {
+                // Запись с тем же ID из более приоритетного каталога (в т.ч. Hidden) перекрывает эту
+                if !seen.insert(id.clone()) {
+                    continue;
+                }
+                let content = match Ini::load_from_file_noescape(&path) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        logger::log_error("DesktopEntries::from_dirs", format!("{}: {e}", path.display()));
+                        continue;
+                    }
+                };
+                if let Some(desktop) = DesktopFile::parse(id, &content, locale) {
+                    index.insert(desktop);
+                }
+            }
+        }
+        index
+    }
+
+    /// Запись для класса окна
+    pub fn find(&self, class: &str) -> Option<&DesktopFile> {
+        let class = class.to_lowercase();
+        [&self.by_wm_class, &self.by_id, &self.by_short_id, &self.by_name]
+            .into_iter()
+            .find_map(|map| map.get(&class))
+            .map(|&i| &self.entries[i])
+    }
+
+    /// Количество проиндексированных записей
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn insert(&mut self, desktop: DesktopFile) {
+        let index = self.entries.len();
+        let id = desktop.id.to_lowercase();
+        let short_id = id.rsplit_once('.').map(|(_, short)| short.to_string());
+        let wm_class = desktop.startup_wm_class.as_deref().map(str::to_lowercase);
+        let name = desktop.name.to_lowercase();
+        let visible = !desktop.no_display;
+        self.entries.push(desktop);
+
+        let entries = &self.entries;
+        let add = |map: &mut HashMap<String, usize>, key: String| {
+            // Видимая запись вытесняет скрытую из меню, но не наоборот
+            match map.get(&key) {
+                Some(&existing) if !(visible && entries[existing].no_display) => {}
+                _ => {
+                    map.insert(key, index);
+                }
+            }
+        };
+        if let Some(wm_class) = wm_class {
+            add(&mut self.by_wm_class, wm_class);
+        }
+        add(&mut self.by_id, id);
+        if let Some(short_id) = short_id {
+            add(&mut self.by_short_id, short_id);
+        }
+        add(&mut self.by_name, name);
+    }
+}
+
+/// Рекурсивно собирает `*.desktop`; ID — путь относительно `applications` с `/` → `-`
+fn collect_desktop_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            collect_desktop_files(root, &path, files);
+        } else if path.extension().is_some_and(|ext| ext == "desktop")
+            && let Ok(relative) = path.with_extension("").strip_prefix(root)
+        {
+            let id = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("-");
+            files.push((id, path));
+        }
+    }
+}
+
+/// Локаль сообщений: `LC_ALL`, `LC_MESSAGES` или `LANG`
+fn current_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .filter(|value| value != "C" && value != "POSIX")
+}
+
+/// Ключи локализации в порядке спецификации:
+/// `lang_COUNTRY@MODIFIER`, `lang_COUNTRY`, `lang@MODIFIER`, `lang`
+fn locale_keys(locale: &str) -> Vec<String> {
+    let (rest, modifier) = match locale.split_once('@') {
+        Some((rest, modifier)) => (rest, Some(modifier)),
+        None => (locale, None),
+    };
+    // Кодировка (`.UTF-8`) при сопоставлении не учитывается
+    let rest = rest.split('.').next().unwrap_or(rest);
+    let (lang, country) = match rest.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (rest, None),
+    };
+
+    let mut keys = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        keys.push(format!("{lang}_{country}@{modifier}"));
+    }
+    if let Some(country) = country {
+        keys.push(format!("{lang}_{country}"));
+    }
+    if let Some(modifier) = modifier {
+        keys.push(format!("{lang}@{modifier}"));
+    }
+    keys.push(lang.to_string());
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Data dir с каталогом `applications` во временном каталоге
+    struct DataDir(TempDir);
+
+    impl DataDir {
+        fn new() -> Self {
+            Self(TempDir::new().unwrap())
+        }
+
+        fn path(&self) -> PathBuf {
+            self.0.path().to_path_buf()
+        }
+
+        fn write(&self, relative: &str, content: &str) {
+            let path = self.0.path().join("applications").join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    fn entry(name: &str, extra: &str) -> String {
+        format!("[Desktop Entry]\nType=Application\nName={name}\nExec={name}\nIcon={name}\n{extra}")
+    }
+
+    #[test]
+    fn matches_by_id_wm_class_and_reverse_dns() {
+        let dir = DataDir::new();
+        dir.write("org.gnome.Nautilus.desktop", &entry("Files", ""));
+        dir.write("code.desktop", &entry("Visual Studio Code", "StartupWMClass=Code\n"));
+        dir.write("kde/org.kde.dolphin.desktop", &entry("Dolphin", ""));
+        let index = DesktopEntries::from_dirs(&[dir.path()], None);
+
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.find("org.gnome.Nautilus").unwrap().name, "Files");
+        assert_eq!(index.find("nautilus").unwrap().name, "Files");
+        assert_eq!(index.find("Code").unwrap().id, "code");
+        assert_eq!(index.find("kde-org.kde.dolphin").unwrap().name, "Dolphin");
+        assert_eq!(index.find("DOLPHIN").unwrap().name, "Dolphin");
+        assert_eq!(index.find("visual studio code").unwrap().id, "code");
+        assert!(index.find("kitty").is_none());
+    }
+
+    #[test]
+    fn wm_class_takes_precedence_over_id() {
+        let dir = DataDir::new();
+        dir.write("chromium.desktop", &entry("Chromium", ""));
+        dir.write("chrome-app.desktop", &entry("Web App", "StartupWMClass=chromium\n"));
+        let index = DesktopEntries::from_dirs(&[dir.path()], None);
+
+        assert_eq!(index.find("chromium").unwrap().name, "Web App");
+    }
+
+    #[test]
+    fn honors_hidden_and_no_display() {
+        let user = DataDir::new();
+        let system = DataDir::new();
+        user.write("firefox.desktop", &entry("Firefox", "Hidden=true\n"));
+        system.write("firefox.desktop", &entry("Firefox", ""));
+        system.write("helper.desktop", &entry("Term", "NoDisplay=true\nStartupWMClass=term\n"));
+        system.write("term.desktop", &entry("Terminal", "StartupWMClass=term\n"));
+        system.write("link.desktop", "[Desktop Entry]\nType=Link\nName=Link\nURL=https://example.com\n");
+        let index = DesktopEntries::from_dirs(&[user.path(), system.path()], None);
+
+        // Hidden в пользовательском каталоге удаляет системную запись
+        assert!(index.find("firefox").is_none());
+        assert!(index.find("link").is_none());
+        assert_eq!(index.find("term").unwrap().id, "term");
+        // Без видимой альтернативы запись с NoDisplay все равно находится
+        assert!(index.find("helper").unwrap().no_display);
+    }
+
+    #[test]
+    fn earlier_data_dirs_win() {
+        let user = DataDir::new();
+        let system = DataDir::new();
+        user.write("app.desktop", &entry("User App", ""));
+        system.write("app.desktop", &entry("System App", ""));
+        let index = DesktopEntries::from_dirs(&[user.path(), system.path()], None);
+
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.find("app").unwrap().name, "User App");
+    }
+
+    #[test]
+    fn uses_localized_name() {
+        let dir = DataDir::new();
+        dir.write(
+            "org.gnome.Nautilus.desktop",
+            "[Desktop Entry]\nName=Files\nName[ru]=Файлы\nName[pt_BR]=Arquivos\nIcon=nautilus\n",
+        );
+        let dirs = [dir.path()];
+
+        let find = |locale| DesktopEntries::from_dirs(&dirs, locale).find("nautilus").unwrap().name.clone();
+        assert_eq!(find(Some("ru_RU.UTF-8")), "Файлы");
+        assert_eq!(find(Some("pt_BR.UTF-8")), "Arquivos");
+        assert_eq!(find(Some("de_DE.UTF-8")), "Files");
+        assert_eq!(find(None), "Files");
+    }
+
+    #[test]
+    fn builds_locale_keys() {
+        assert_eq!(locale_keys("sr_RS.UTF-8@latin"), ["sr_RS@latin", "sr_RS", "sr@latin", "sr"]);
+        assert_eq!(locale_keys("ru_RU.UTF-8"), ["ru_RU", "ru"]);
+        assert_eq!(locale_keys("de"), ["de"]);
+    }
+}
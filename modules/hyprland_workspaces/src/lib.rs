@@ -1,21 +1,28 @@
-use std::{collections::HashMap, fmt::Display, sync::Mutex};
+use std::{collections::HashMap, fmt::Display};
 
 use anyhow::Result;
 use derive_more::Display as DeriveDisplay;
 use helpers::icon_fetcher;
 use hyprland::{
-    data::{Clients, Workspace as ActiveWorkspace, Workspaces},
-    shared::{HyprData, HyprDataActive},
+    data::{Client, Clients, Monitors, Workspace as ActiveWorkspace, Workspaces},
+    shared::{HyprData, HyprDataActive, HyprDataActiveOptional},
 };
-use ini::Ini;
-use once_cell::sync::Lazy;
+mod desktop;
+mod rules;
+
+pub use desktop::{DesktopEntries, DesktopFile};
+pub use rules::{ClientRule, ClientRules};
 
 pub type HyprWorkspacesMap = HashMap<i32, HyprWorkspace>;
 
 #[derive(Debug, DeriveDisplay)]
 #[display("class: [{class}] title({initial_title}): {title} (workspace: {workspace_id})")]
 pub struct HyprlandClient {
+    /// Адрес окна в Hyprland — стабильный ключ клиента
+    pub address: String,
     pub class: String,
+    /// Имя приложения: из правила, desktop-файла или класс окна
+    pub name: String,
     pub title: String,
     pub initial_title: String,
     pub workspace_id: i32,
@@ -26,11 +33,37 @@ pub struct HyprlandClient {
 #[derive(Debug)]
 pub struct HyprWorkspace {
     pub id: i32,
+    pub name: String,
+    /// Special (scratchpad) workspace; у таких workspace'ов отрицательный id
+    pub special: bool,
     pub monitor: String,
     pub monitor_id: Option<i128>,
     pub clients: Vec<HyprlandClient>,
 }
 
+impl HyprWorkspace {
+    /// Еще не созданный workspace без окон (например, постоянный workspace из конфигурации)
+    pub fn empty(id: i32, monitor: &str) -> Self {
+        Self {
+            id,
+            name: id.to_string(),
+            special: false,
+            monitor: monitor.to_string(),
+            monitor_id: None,
+            clients: Vec::new(),
+        }
+    }
+
+    /// Имя special workspace'а для `togglespecialworkspace` (`special:term` → `term`).
+    /// `None` для безымянного special workspace'а и обычных workspace'ов
+    pub fn special_name(&self) -> Option<&str> {
+        if !self.special {
+            return None;
+        }
+        self.name.strip_prefix("special:")
+    }
+}
+
 impl Display for HyprWorkspace {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -38,10 +71,9 @@ impl Display for HyprWorkspace {
             "Workspace {{id: {}, monitor({}): {}, clients: {}}}",
             self.id,
             self.monitor,
-            if self.monitor_id.is_some() {
-                self.monitor_id.unwrap().to_string()
-            } else {
-                "None".to_string()
+            match self.monitor_id {
+                Some(monitor_id) => monitor_id.to_string(),
+                None => "None".to_string(),
             },
             self.clients.len()
         )
@@ -52,24 +84,85 @@ impl Display for HyprWorkspace {
 pub struct HyprWorkspaces {
     pub map: HyprWorkspacesMap,
     pub active_id: Option<i32>,
+    /// Активный workspace каждого монитора (имя монитора → id)
+    pub monitor_active: HashMap<String, i32>,
+    /// Открытый special workspace каждого монитора (имя монитора → id)
+    pub monitor_special: HashMap<String, i32>,
+    /// Адрес окна в фокусе
+    pub active_window: Option<String>,
 }
 
 impl HyprWorkspaces {
-    pub fn init() -> Result<Self> {
-        let (ws_map, active_id) = Self::get_workspaces()?;
+    /// Снимок workspace'ов; `rules` применяются к окнам до поиска desktop-файлов
+    pub fn init(rules: &ClientRules) -> Result<Self> {
+        let (ws_map, active_id) = Self::get_workspaces(rules)?;
+        let (monitor_active, monitor_special) = Self::get_monitor_state();
+        let active_window = Self::get_active_window();
         Ok(HyprWorkspaces {
             map: ws_map,
             active_id,
+            monitor_active,
+            monitor_special,
+            active_window,
         })
     }
 
-    fn get_workspaces() -> Result<(HyprWorkspacesMap, Option<i32>)> {
+    /// Активный workspace на мониторе; если данные о мониторах недоступны —
+    /// глобально активный workspace
+    pub fn active_on(&self, monitor: &str) -> Option<i32> {
+        if self.monitor_active.is_empty() {
+            return self.active_id;
+        }
+        self.monitor_active.get(monitor).copied()
+    }
+
+    /// Открытый special workspace на мониторе
+    pub fn special_on(&self, monitor: &str) -> Option<i32> {
+        self.monitor_special.get(monitor).copied()
+    }
+
+    fn get_active_window() -> Option<String> {
+        match Client::get_active() {
+            Ok(client) => client.map(|client| client.address.to_string()),
+            Err(e) => {
+                logger::log_warning("HyprWorkspaces::get_active_window", format!("Failed to get active window: {e}"));
+                None
+            }
+        }
+    }
+
+    /// Активные и открытые special workspace'ы мониторов
+    fn get_monitor_state() -> (HashMap<String, i32>, HashMap<String, i32>) {
+        let monitors = match Monitors::get() {
+            Ok(monitors) => monitors,
+            Err(e) => {
+                logger::log_warning("HyprWorkspaces::get_monitor_state", format!("Failed to get monitors: {e}"));
+                return (HashMap::new(), HashMap::new());
+            }
+        };
+
+        let active = monitors
+            .iter()
+            .map(|monitor| (monitor.name.clone(), monitor.active_workspace.id))
+            .collect();
+        // id 0 — special workspace на мониторе не открыт
+        let special = monitors
+            .iter()
+            .filter(|monitor| monitor.special_workspace.id != 0)
+            .map(|monitor| (monitor.name.clone(), monitor.special_workspace.id))
+            .collect();
+        (active, special)
+    }
+
+    fn get_workspaces(rules: &ClientRules) -> Result<(HyprWorkspacesMap, Option<i32>)> {
         let mut hypr_ws: HashMap<i32, HyprWorkspace> = HashMap::new();
 
         let workspaces = Workspaces::get()?;
         for ws_i in workspaces.iter() {
             let workspace = HyprWorkspace {
                 id: ws_i.id,
+                name: ws_i.name.clone(),
+                special: ws_i.id < 0,
                 monitor: ws_i.monitor.clone(),
                 monitor_id: ws_i.monitor_id,
                 clients: Vec::new(),
@@ -80,18 +173,34 @@ impl HyprWorkspaces {
 
         let clients = Clients::get()?;
         for client in clients.iter() {
-            let desktop_file = DesktopFile::load(&client.class)?;
-            let icons = if let Some(df) = &desktop_file {
-                if let Some(icon_name) = &df.icon {
-                    icon_fetcher(icon_name)?
-                } else {
-                    icon_fetcher(&client.class)?
-                }
+            let rule = rules.find(&client.class, &client.title);
+            if rule.is_some_and(|rule| rule.hide) {
+                continue;
+            }
+            let rule_icon = rule.and_then(|rule| rule.icon_for(&client.class));
+            let rule_name = rule.and_then(|rule| rule.name_for(&client.class));
+
+            // Desktop-файл не нужен, если правило задает и иконку, и имя
+            let desktop_file = if rule_icon.is_some() && rule_name.is_some() {
+                None
             } else {
-                icon_fetcher(&client.class)?
+                DesktopFile::load(&client.class)
             };
+            let icons = match &rule_icon {
+                Some(icon) if std::path::Path::new(icon).is_absolute() => vec![icon.clone()],
+                Some(icon) => icon_fetcher(icon)?,
+                None => match desktop_file.as_ref().and_then(|df| df.icon.as_ref()) {
+                    Some(icon_name) => icon_fetcher(icon_name)?,
+                    None => icon_fetcher(&client.class)?,
+                },
+            };
+            let name = rule_name
+                .or_else(|| desktop_file.as_ref().map(|df| df.name.clone()))
+                .unwrap_or_else(|| client.class.clone());
             let hypr_client = HyprlandClient {
+                address: client.address.to_string(),
                 class: client.class.clone(),
+                name,
                 title: client.title.clone(),
                 initial_title: client.initial_title.clone(),
                 workspace_id: client.workspace.id,
@@ -114,73 +223,3 @@ impl HyprWorkspaces {
         Ok((hypr_ws, active_id))
     }
 }
-
-const DESKTOP_FILE_DIRS: [&str; 3] = [
-    "/usr/share/applications",
-    "/usr/local/share/applications",
-    "~/.local/share/applications",
-];
-
-static DESKTOP_CACHE: Lazy<Mutex<HashMap<String, Option<DesktopFile>>>> =
-    Lazy::new(|| Mutex::new(HashMap::new()));
-
-#[derive(Debug, DeriveDisplay, Clone)]
-#[display("DesktopFile {{ name: {name}, exec: {exec}, icon: {icon:?} }}")]
-pub struct DesktopFile {
-    pub name: String,
-    pub exec: String,
-    pub icon: Option<String>,
-}
-
-impl DesktopFile {
-    pub fn load(app_class_name: &str) -> Result<Option<Self>> {
-        if let Some(cached) = DESKTOP_CACHE
-            .lock()
-            .ok()
-            .and_then(|cache| cache.get(app_class_name).cloned())
-        {
-            return Ok(cached);
-        }
-
-        let Some(file_path) = Self::find(app_class_name) else {
-            logger::log_info("DesktopFile::load", format!("Desktop file for '{}' not found", app_class_name));
-            return Ok(None);
-        };
-        logger::log_info("DesktopFile::load", format!("Loading desktop file from: {}", file_path));
-        let content = Ini::load_from_file(&file_path)?;
-        let name = content
-            .get_from(Some("Desktop Entry"), "Name")
-            .unwrap_or("")
-            .to_string();
-        let exec = content
-            .get_from(Some("Desktop Entry"), "Exec")
-            .unwrap_or("")
-            .to_string();
-        let icon = content
-            .get_from(Some("Desktop Entry"), "Icon")
-            .map(|s| s.to_string());
-
-        let desktop = Some(DesktopFile { name, exec, icon });
-
-        if let Ok(mut cache) = DESKTOP_CACHE.lock() {
-            cache.insert(app_class_name.to_string(), desktop.clone());
-        }
-
-        Ok(desktop)
-    }
-
-    fn find(app_class_name: &str) -> Option<String> {
-        for dir in DESKTOP_FILE_DIRS.iter() {
-            let path = format!("{}/{}.desktop", dir, app_class_name);
-            let path_lower = format!("{}/{}.desktop", dir, app_class_name.to_lowercase());
-            if std::path::Path::new(&path_lower).exists() {
-                return Some(path_lower);
-            }
-            if std::path::Path::new(&path).exists() {
-                return Some(path);
-            }
-        }
-        None
-    }
-}
-
@@ -0,0 +1,147 @@
+use anyhow::{Result, bail};
+use regex::Regex;
+
+/// Правило для окон: совпадение по классу и/или заголовку (regex, поиск подстроки;
+/// для точного совпадения используйте `^...$`).
+///
+/// Применяется до поиска desktop-файла: может заменить иконку, имя приложения
+/// или скрыть окно. В `icon` и `name` можно ссылаться на группы из regex класса
+/// (`$1`, `${name}`), например `steam_app_(\d+)` → `steam_icon_$1`.
+#[derive(Debug, Clone)]
+pub struct ClientRule {
+    class: Option<Regex>,
+    title: Option<Regex>,
+    /// Имя иконки из темы или путь к файлу
+    pub icon: Option<String>,
+    /// Имя приложения для подсказок
+    pub name: Option<String>,
+    /// Не показывать окно
+    pub hide: bool,
+}
+
+impl ClientRule {
+    /// Создает правило; хотя бы один из шаблонов обязателен
+    pub fn new(class: Option<&str>, title: Option<&str>) -> Result<Self> {
+        if class.is_none() && title.is_none() {
+            bail!("rule must match `class` or `title`");
+        }
+        Ok(Self {
+            class: class.map(Regex::new).transpose()?,
+            title: title.map(Regex::new).transpose()?,
+            icon: None,
+            name: None,
+            hide: false,
+        })
+    }
+
+    /// Совпадает ли правило с окном
+    pub fn matches(&self, class: &str, title: &str) -> bool {
+        self.class.as_ref().is_none_or(|re| re.is_match(class))
+            && self.title.as_ref().is_none_or(|re| re.is_match(title))
+    }
+
+    /// Иконка из правила с подставленными группами из класса окна
+    pub fn icon_for(&self, class: &str) -> Option<String> {
+        self.icon.as_deref().map(|icon| self.expand(icon, class))
+    }
+
+    /// Имя из правила с подставленными группами из класса окна
+    pub fn name_for(&self, class: &str) -> Option<String> {
+        self.name.as_deref().map(|name| self.expand(name, class))
+    }
+
+    fn expand(&self, template: &str, class: &str) -> String {
+        let Some(captures) = self.class.as_ref().and_then(|re| re.captures(class)) else {
+            return template.to_string();
+        };
+        let mut expanded = String::new();
+        captures.expand(template, &mut expanded);
+        expanded
+    }
+}
+
+impl PartialEq for ClientRule {
+    fn eq(&self, other: &Self) -> bool {
+        let pattern = |re: &Option<Regex>| re.as_ref().map(|re| re.as_str().to_string());
+        pattern(&self.class) == pattern(&other.class)
+            && pattern(&self.title) == pattern(&other.title)
+            && self.icon == other.icon
+            && self.name == other.name
+            && self.hide == other.hide
+    }
+}
+
+/// Список правил; применяется первое совпавшее
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientRules(pub Vec<ClientRule>);
+
+impl ClientRules {
+    /// Первое правило, совпавшее с окном
+    pub fn find(&self, class: &str, title: &str) -> Option<&ClientRule> {
+        self.0.iter().find(|rule| rule.matches(class, title))
+    }
+
+    /// Есть ли правила по заголовку: тогда смена заголовка может скрыть
+    /// или переименовать окно
+    pub fn match_titles(&self) -> bool {
+        self.0.iter().any(|rule| rule.title.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(class: Option<&str>, title: Option<&str>) -> ClientRule {
+        ClientRule::new(class, title).unwrap()
+    }
+
+    #[test]
+    fn matches_class_and_title() {
+        let rule = rule(Some("^firefox$"), Some("YouTube"));
+        assert!(rule.matches("firefox", "Music - YouTube"));
+        assert!(!rule.matches("firefox", "GitHub"));
+        assert!(!rule.matches("firefox-esr", "YouTube"));
+    }
+
+    #[test]
+    fn detects_title_rules() {
+        assert!(!ClientRules(vec![rule(Some("firefox"), None)]).match_titles());
+        assert!(ClientRules(vec![rule(Some("firefox"), None), rule(None, Some("YouTube"))]).match_titles());
+    }
+
+    #[test]
+    fn requires_a_pattern() {
+        assert!(ClientRule::new(None, None).is_err());
+        assert!(ClientRule::new(Some("("), None).is_err());
+    }
+
+    #[test]
+    fn expands_class_captures() {
+        let mut steam = rule(Some(r"^steam_app_(\d+)$"), None);
+        steam.icon = Some("steam_icon_$1".to_string());
+        steam.name = Some("Steam game ${1}".to_string());
+        assert_eq!(steam.icon_for("steam_app_1234").as_deref(), Some("steam_icon_1234"));
+        assert_eq!(steam.name_for("steam_app_1234").as_deref(), Some("Steam game 1234"));
+
+        let mut title_only = rule(None, Some("Discord"));
+        title_only.icon = Some("discord".to_string());
+        assert_eq!(title_only.icon_for("electron").as_deref(), Some("discord"));
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let mut hidden = rule(Some("^xwaylandvideobridge$"), None);
+        hidden.hide = true;
+        let mut electron = rule(Some("(?i)electron"), None);
+        electron.name = Some("Electron".to_string());
+        let mut catch_all = rule(Some(".*"), None);
+        catch_all.name = Some("Other".to_string());
+        let rules = ClientRules(vec![hidden, electron, catch_all]);
+
+        assert!(rules.find("xwaylandvideobridge", "").unwrap().hide);
+        assert_eq!(rules.find("Electron", "").unwrap().name.as_deref(), Some("Electron"));
+        assert_eq!(rules.find("kitty", "").unwrap().name.as_deref(), Some("Other"));
+        assert!(ClientRules::default().find("kitty", "").is_none());
+    }
+}
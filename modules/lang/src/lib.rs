@@ -1,12 +1,76 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
+use hyprland::ctl::switch_xkb_layout::{self, SwitchXKBLayoutCmdTypes};
 use hyprland::data::Devices;
 use hyprland::shared::HyprData;
 
-/// Получает текущую раскладку клавиатуры основной клавиатуры.
+mod xkb;
+
+pub use xkb::{LAYOUTS, XkbLayout, country_flag, find_layout};
+
+/// Раскладка клавиатуры
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyboardLayout {
+    /// Имя устройства, как в событии `activelayout`
+    pub keyboard: String,
+    /// Название активной раскладки, например `Russian`
+    pub layout: String,
+    /// Раскладки, настроенные для клавиатуры, в порядке переключения
+    pub layouts: Vec<ConfiguredLayout>,
+}
+
+/// Раскладка из настроек клавиатуры (`kb_layout` и `kb_variant`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfiguredLayout {
+    /// Код XKB, например `ru`
+    pub code: String,
+    /// Вариант, например `phonetic`; пустой, если не задан
+    pub variant: String,
+    /// Название для меню: из таблицы XKB, иначе код
+    pub name: String,
+}
+
+/// Разбирает `kb_layout` и `kb_variant`: `us,ru` и `,phonetic`
+pub fn configured_layouts(layout: &str, variant: &str) -> Vec<ConfiguredLayout> {
+    let mut variants = variant.split(',').map(str::trim);
+    layout
+        .split(',')
+        .map(str::trim)
+        .filter(|code| !code.is_empty())
+        .map(|code| {
+            let variant = variants.next().unwrap_or("").to_string();
+            let base = find_layout(code).map_or(code, |known| known.name);
+            let name = if variant.is_empty() { base.to_string() } else { format!("{base} ({variant})") };
+            ConfiguredLayout { code: code.to_string(), variant, name }
+        })
+        .collect()
+}
+
+/// Куда переключить раскладку
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayoutSwitch {
+    Next,
+    Previous,
+    /// Номер раскладки в [`KeyboardLayout::layouts`]
+    Index(u8),
+}
+
+/// Переключает раскладку клавиатуры (`hyprctl switchxkblayout`)
+pub fn switch_layout(keyboard: &str, switch: LayoutSwitch) -> Result<()> {
+    let cmd = match switch {
+        LayoutSwitch::Next => SwitchXKBLayoutCmdTypes::Next,
+        LayoutSwitch::Previous => SwitchXKBLayoutCmdTypes::Previous,
+        LayoutSwitch::Index(index) => SwitchXKBLayoutCmdTypes::Id(index),
+    };
+    switch_xkb_layout::call(keyboard, cmd)?;
+    Ok(())
+}
+
+/// Получает имя и раскладку основной клавиатуры.
 ///
-/// Возвращает `active_keymap` основной клавиатуры или ошибку, если
-/// основная клавиатура не найдена.
-pub fn get_current_layout() -> Result<String> {
+/// Возвращает ошибку, если основная клавиатура не найдена.
+pub fn get_main_keyboard_layout() -> Result<KeyboardLayout> {
     let devices = Devices::get()?;
     let keyboards = devices.keyboards;
 
@@ -16,30 +80,146 @@ pub fn get_current_layout() -> Result<String> {
         return Err(anyhow::anyhow!("No main keyboard found"));
     };
 
-    Ok(main_keyboard.active_keymap.clone())
+    Ok(KeyboardLayout {
+        keyboard: main_keyboard.name.clone(),
+        layout: main_keyboard.active_keymap.clone(),
+        layouts: configured_layouts(&main_keyboard.layout, &main_keyboard.variant),
+    })
 }
 
-/// Получает флаг для текущей раскладки клавиатуры.
-///
-/// Возвращает эмодзи флага в зависимости от раскладки:
-/// - 🇷🇺 для русской раскладки
-/// - 🇺🇸 для английской (US) раскладки
-/// - исходную раскладку, если не удалось определить
-pub fn get_layout_flag() -> Result<String> {
-    let layout = get_current_layout()?;
-    let layout_lower = layout.to_lowercase();
+/// Как показывать раскладку
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum LayoutFormat {
+    /// Флаг страны: 🇷🇺
+    #[default]
+    Flag,
+    /// Короткое обозначение: `RU`
+    Short,
+    /// Название как в `active_keymap`: `Russian (phonetic)`
+    Full,
+    /// Шаблон с подстановками `{flag}`, `{short}`, `{full}`, `{code}`, `{country}` и `{variant}`
+    Custom(String),
+}
 
-    // Проверяем различные варианты названий русской раскладки
-    if layout_lower.contains("ru") || layout_lower.contains("russian") || layout_lower.contains("русск") {
-        return Ok("🇷🇺".to_string());
+impl LayoutFormat {
+    /// `flag`, `short` и `full` — встроенные форматы, любая другая строка — шаблон
+    pub fn parse(format: &str) -> Self {
+        match format {
+            "flag" => Self::Flag,
+            "short" => Self::Short,
+            "full" => Self::Full,
+            template => Self::Custom(template.to_string()),
+        }
     }
+}
 
-    // Проверяем различные варианты названий английской (US) раскладки
-    if layout_lower.contains("us") || layout_lower.contains("english") || layout_lower.contains("en") {
-        return Ok("🇺🇸".to_string());
+/// Форматирует название раскладки из `active_keymap`.
+///
+/// `overrides` задает готовую подпись по названию раскладки или коду XKB и
+/// имеет приоритет над форматом. Для неизвестных раскладок флагом служит
+/// короткое обозначение, а им — первые две буквы названия.
+pub fn format_layout(layout: &str, format: &LayoutFormat, overrides: &HashMap<String, String>) -> String {
+    let known = find_layout(layout);
+    if let Some(label) = overrides
+        .get(layout)
+        .or_else(|| known.and_then(|known| overrides.get(known.code)))
+    {
+        return label.clone();
     }
 
-    // Если не удалось определить, возвращаем исходную раскладку
-    Ok(layout)
+    let short = known.map_or_else(
+        || layout.chars().filter(|c| c.is_alphabetic()).take(2).collect::<String>().to_uppercase(),
+        |known| known.short.to_string(),
+    );
+    let flag = || known.and_then(XkbLayout::flag).unwrap_or_else(|| short.clone());
+    match format {
+        LayoutFormat::Flag => flag(),
+        LayoutFormat::Short => short.clone(),
+        LayoutFormat::Full => layout.to_string(),
+        LayoutFormat::Custom(template) => {
+            let variant = layout
+                .split_once(" (")
+                .map_or("", |(_, variant)| variant.strip_suffix(')').unwrap_or(variant));
+            template
+                .replace("{flag}", &flag())
+                .replace("{short}", &short)
+                .replace("{full}", layout)
+                .replace("{code}", known.map_or("", |known| known.code))
+                .replace("{country}", known.and_then(|known| known.country).unwrap_or(""))
+                .replace("{variant}", variant)
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(layout: &str, format: &str) -> String {
+        format_layout(layout, &LayoutFormat::parse(format), &HashMap::new())
+    }
+
+    #[test]
+    fn formats_builtin_styles() {
+        assert_eq!(format("Russian (phonetic, US)", "flag"), "🇷🇺");
+        assert_eq!(format("English (US)", "flag"), "🇺🇸");
+        assert_eq!(format("English (UK)", "flag"), "🇬🇧");
+        assert_eq!(format("Belarusian", "flag"), "🇧🇾");
+        assert_eq!(format("English (US)", "short"), "EN");
+        assert_eq!(format("Ukrainian", "short"), "UK");
+        assert_eq!(format("German (Switzerland)", "short"), "DE");
+        assert_eq!(format("Russian (phonetic)", "full"), "Russian (phonetic)");
+    }
+
+    #[test]
+    fn formats_custom_template() {
+        let template = "{flag} {short} [{code}/{country}] {variant}";
+        assert_eq!(format("Russian (phonetic, US)", template), "🇷🇺 RU [ru/RU] phonetic, US");
+        assert_eq!(format("German", template), "🇩🇪 DE [de/DE] ");
+        assert_eq!(format("English (UK)", "{full}: {short}"), "English (UK): EN");
+    }
+
+    #[test]
+    fn falls_back_for_unknown_layouts() {
+        assert_eq!(format("Klingon", "flag"), "KL");
+        assert_eq!(format("Klingon", "short"), "KL");
+        assert_eq!(format("Klingon", "{code}{country}|{flag}"), "|KL");
+        // У арабской раскладки нет страны, флагом служит короткое обозначение
+        assert_eq!(format("Arabic", "flag"), "AR");
+    }
+
+    #[test]
+    fn applies_overrides() {
+        let overrides = HashMap::from([
+            ("English (US)".to_string(), "us".to_string()),
+            ("ru".to_string(), "ЙЦ".to_string()),
+        ]);
+        let format = |layout| format_layout(layout, &LayoutFormat::Short, &overrides);
+        assert_eq!(format("English (US)"), "us");
+        // Переопределение по коду действует на все варианты раскладки
+        assert_eq!(format("Russian (phonetic)"), "ЙЦ");
+        assert_eq!(format("English (UK)"), "EN");
+    }
+
+    #[test]
+    fn parses_configured_layouts() {
+        let layouts = configured_layouts("us,ru,de", ",phonetic");
+        let names: Vec<&str> = layouts.iter().map(|layout| layout.name.as_str()).collect();
+        assert_eq!(names, ["English (US)", "Russian (phonetic)", "German"]);
+        assert_eq!(layouts[1].code, "ru");
+        assert_eq!(layouts[1].variant, "phonetic");
+        assert_eq!(layouts[2].variant, "");
+
+        assert_eq!(configured_layouts("xx", "")[0].name, "xx");
+        assert!(configured_layouts("", "").is_empty());
+    }
+
+    #[test]
+    fn parses_format() {
+        assert_eq!(LayoutFormat::parse("flag"), LayoutFormat::Flag);
+        assert_eq!(LayoutFormat::parse("short"), LayoutFormat::Short);
+        assert_eq!(LayoutFormat::parse("full"), LayoutFormat::Full);
+        assert_eq!(LayoutFormat::parse("{short}"), LayoutFormat::Custom("{short}".to_string()));
+        assert_eq!(format_layout("English (US)", &LayoutFormat::default(), &HashMap::new()), "🇺🇸");
+    }
+}
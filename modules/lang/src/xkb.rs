@@ -0,0 +1,348 @@
+/// Раскладка XKB: код, название из `evdev.lst`, страна и короткое обозначение
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct XkbLayout {
+    /// Код раскладки, например `ru`
+    pub code: &'static str,
+    /// Название, которое Hyprland показывает в `active_keymap`, например `Russian`
+    pub name: &'static str,
+    /// Код страны ISO 3166-1 alpha-2; `None` для раскладок без страны (арабская, эсперанто)
+    pub country: Option<&'static str>,
+    /// Короткое обозначение языка, например `RU` или `EN`
+    pub short: &'static str,
+}
+
+impl XkbLayout {
+    /// Эмодзи флага страны; `None`, если у раскладки нет страны
+    pub fn flag(&self) -> Option<String> {
+        self.country.map(country_flag)
+    }
+}
+
+/// Флаг из региональных индикаторов: `RU` → 🇷🇺
+pub fn country_flag(country: &str) -> String {
+    country
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .filter_map(|c| char::from_u32(0x1F1E6 + (c.to_ascii_uppercase() as u32 - 'A' as u32)))
+        .collect()
+}
+
+const fn layout(code: &'static str, name: &'static str, country: &'static str, short: &'static str) -> XkbLayout {
+    XkbLayout { code, name, country: Some(country), short }
+}
+
+const fn no_country(code: &'static str, name: &'static str, short: &'static str) -> XkbLayout {
+    XkbLayout { code, name, country: None, short }
+}
+
+/// Раскладки XKB. Для одного кода может быть несколько названий: варианты,
+/// которые XKB называет по другому языку (`English (Dvorak)` у `us`,
+/// `French (Switzerland)` у `ch`). Первая запись кода — основная.
+pub const LAYOUTS: &[XkbLayout] = &[
+    layout("us", "English (US)", "US", "EN"),
+    layout("us", "English", "US", "EN"),
+    layout("us", "Cherokee", "US", "CHR"),
+    layout("us", "Hawaiian", "US", "HAW"),
+    layout("gb", "English (UK)", "GB", "EN"),
+    layout("gb", "Scottish Gaelic", "GB", "GD"),
+    layout("gb", "Welsh", "GB", "CY"),
+    layout("au", "English (Australian)", "AU", "EN"),
+    layout("nz", "English (New Zealand)", "NZ", "EN"),
+    layout("nz", "Maori", "NZ", "MI"),
+    layout("za", "English (South Africa)", "ZA", "EN"),
+    layout("ng", "English (Nigeria)", "NG", "EN"),
+    layout("gh", "English (Ghana)", "GH", "EN"),
+    layout("cm", "English (Cameroon)", "CM", "EN"),
+    layout("in", "Indian", "IN", "IN"),
+    layout("in", "English (India)", "IN", "EN"),
+    layout("in", "Hindi", "IN", "HI"),
+    layout("in", "Bangla (India)", "IN", "BN"),
+    layout("in", "Tamil", "IN", "TA"),
+    layout("in", "Telugu", "IN", "TE"),
+    layout("in", "Kannada", "IN", "KN"),
+    layout("in", "Malayalam", "IN", "ML"),
+    layout("in", "Gujarati", "IN", "GU"),
+    layout("in", "Punjabi (Gurmukhi)", "IN", "PA"),
+    layout("in", "Marathi", "IN", "MR"),
+    layout("ie", "Irish", "IE", "GA"),
+    layout("ca", "French (Canada)", "CA", "FR"),
+    layout("ca", "English (Canada)", "CA", "EN"),
+    layout("ca", "Canadian (CSA)", "CA", "FR"),
+    layout("ca", "Inuktitut", "CA", "IU"),
+    layout("ru", "Russian", "RU", "RU"),
+    layout("ua", "Ukrainian", "UA", "UK"),
+    layout("by", "Belarusian", "BY", "BE"),
+    layout("kz", "Kazakh", "KZ", "KK"),
+    layout("uz", "Uzbek", "UZ", "UZ"),
+    layout("kg", "Kyrgyz", "KG", "KY"),
+    layout("tj", "Tajik", "TJ", "TG"),
+    layout("tm", "Turkmen", "TM", "TK"),
+    layout("az", "Azerbaijani", "AZ", "AZ"),
+    layout("am", "Armenian", "AM", "HY"),
+    layout("ge", "Georgian", "GE", "KA"),
+    layout("md", "Moldavian", "MD", "RO"),
+    layout("mn", "Mongolian", "MN", "MN"),
+    layout("de", "German", "DE", "DE"),
+    layout("at", "German (Austria)", "AT", "DE"),
+    layout("ch", "German (Switzerland)", "CH", "DE"),
+    layout("ch", "French (Switzerland)", "CH", "FR"),
+    layout("li", "German (Liechtenstein)", "LI", "DE"),
+    layout("lu", "Luxembourgish", "LU", "LB"),
+    layout("fr", "French", "FR", "FR"),
+    layout("be", "Belgian", "BE", "BE"),
+    layout("nl", "Dutch", "NL", "NL"),
+    layout("es", "Spanish", "ES", "ES"),
+    layout("es", "Catalan", "ES", "CA"),
+    layout("es", "Asturian", "ES", "AST"),
+    no_country("latam", "Spanish (Latin American)", "ES"),
+    layout("pt", "Portuguese", "PT", "PT"),
+    layout("br", "Portuguese (Brazil)", "BR", "PT"),
+    layout("it", "Italian", "IT", "IT"),
+    layout("it", "Friulian", "IT", "FUR"),
+    layout("it", "Sicilian", "IT", "SCN"),
+    layout("mt", "Maltese", "MT", "MT"),
+    layout("pl", "Polish", "PL", "PL"),
+    layout("cz", "Czech", "CZ", "CS"),
+    layout("sk", "Slovak", "SK", "SK"),
+    layout("hu", "Hungarian", "HU", "HU"),
+    layout("ro", "Romanian", "RO", "RO"),
+    layout("bg", "Bulgarian", "BG", "BG"),
+    layout("rs", "Serbian", "RS", "SR"),
+    layout("rs", "Pannonian Rusyn", "RS", "RUE"),
+    layout("hr", "Croatian", "HR", "HR"),
+    layout("si", "Slovenian", "SI", "SL"),
+    layout("ba", "Bosnian", "BA", "BS"),
+    layout("mk", "Macedonian", "MK", "MK"),
+    layout("me", "Montenegrin", "ME", "ME"),
+    layout("al", "Albanian", "AL", "SQ"),
+    layout("gr", "Greek", "GR", "EL"),
+    layout("tr", "Turkish", "TR", "TR"),
+    layout("tr", "Kurdish (Turkey)", "TR", "KU"),
+    layout("cy", "Greek (Cyprus)", "CY", "EL"),
+    layout("se", "Swedish", "SE", "SV"),
+    layout("se", "Northern Saami (Sweden)", "SE", "SE"),
+    layout("no", "Norwegian", "NO", "NO"),
+    layout("no", "Northern Saami (Norway)", "NO", "SE"),
+    layout("dk", "Danish", "DK", "DA"),
+    layout("fi", "Finnish", "FI", "FI"),
+    layout("fi", "Northern Saami (Finland)", "FI", "SE"),
+    layout("is", "Icelandic", "IS", "IS"),
+    layout("fo", "Faroese", "FO", "FO"),
+    layout("ee", "Estonian", "EE", "ET"),
+    layout("lv", "Latvian", "LV", "LV"),
+    layout("lt", "Lithuanian", "LT", "LT"),
+    layout("il", "Hebrew", "IL", "HE"),
+    no_country("ara", "Arabic", "AR"),
+    layout("eg", "Arabic (Egypt)", "EG", "AR"),
+    layout("ma", "Arabic (Morocco)", "MA", "AR"),
+    layout("ma", "Berber (Morocco)", "MA", "BER"),
+    layout("sy", "Arabic (Syria)", "SY", "AR"),
+    layout("sy", "Syriac", "SY", "SYC"),
+    layout("dz", "Berber (Algeria)", "DZ", "BER"),
+    layout("dz", "Arabic (Algeria)", "DZ", "AR"),
+    layout("tn", "Arabic (Tunisia)", "TN", "AR"),
+    layout("iq", "Iraqi", "IQ", "AR"),
+    layout("iq", "Kurdish (Iraq)", "IQ", "KU"),
+    layout("ir", "Persian", "IR", "FA"),
+    layout("af", "Dari", "AF", "FA"),
+    layout("af", "Pashto", "AF", "PS"),
+    layout("pk", "Urdu (Pakistan)", "PK", "UR"),
+    layout("pk", "Urdu", "PK", "UR"),
+    layout("bd", "Bangla", "BD", "BN"),
+    layout("np", "Nepali", "NP", "NE"),
+    layout("lk", "Sinhala", "LK", "SI"),
+    layout("mv", "Dhivehi", "MV", "DV"),
+    layout("bt", "Dzongkha", "BT", "DZ"),
+    layout("th", "Thai", "TH", "TH"),
+    layout("la", "Lao", "LA", "LO"),
+    layout("vn", "Vietnamese", "VN", "VI"),
+    layout("kh", "Khmer (Cambodia)", "KH", "KM"),
+    layout("mm", "Burmese", "MM", "MY"),
+    layout("cn", "Chinese", "CN", "ZH"),
+    layout("cn", "Tibetan", "CN", "BO"),
+    layout("cn", "Uyghur", "CN", "UG"),
+    layout("tw", "Taiwanese", "TW", "ZH"),
+    layout("jp", "Japanese", "JP", "JA"),
+    layout("kr", "Korean", "KR", "KO"),
+    layout("id", "Indonesian", "ID", "ID"),
+    layout("my", "Malay", "MY", "MS"),
+    layout("ph", "Filipino", "PH", "TL"),
+    layout("ke", "Swahili (Kenya)", "KE", "SW"),
+    layout("ke", "Kikuyu", "KE", "KI"),
+    layout("tz", "Swahili (Tanzania)", "TZ", "SW"),
+    layout("et", "Amharic", "ET", "AM"),
+    layout("er", "Tigrinya", "ER", "TI"),
+    layout("sn", "Wolof", "SN", "WO"),
+    layout("ml", "Bambara", "ML", "BM"),
+    layout("bw", "Tswana", "BW", "TN"),
+    layout("cd", "French (Democratic Republic of the Congo)", "CD", "FR"),
+    layout("gn", "N'Ko (AZERTY)", "GN", "NQO"),
+    layout("tg", "French (Togo)", "TG", "FR"),
+    no_country("epo", "Esperanto", "EO"),
+    no_country("brai", "Braille", "BRAI"),
+];
+
+/// Ищет раскладку по названию из `active_keymap` или по коду XKB.
+///
+/// Название сопоставляется с самой длинной записью таблицы, с которой оно
+/// совпадает целиком или как вариант: `Russian (phonetic, US)` → `Russian`,
+/// `English (UK, extended, Windows)` → `English (UK)`. Код можно указать
+/// с вариантом: `us(intl)`.
+pub fn find_layout(keymap: &str) -> Option<&'static XkbLayout> {
+    let keymap = keymap.trim();
+    if keymap.is_empty() {
+        return None;
+    }
+
+    let code = keymap.split('(').next().unwrap_or(keymap).trim();
+    if let Some(layout) = LAYOUTS.iter().find(|layout| layout.code.eq_ignore_ascii_case(code)) {
+        return Some(layout);
+    }
+
+    let lower = keymap.to_lowercase();
+    LAYOUTS
+        .iter()
+        .filter(|layout| name_matches(&lower, &layout.name.to_lowercase()))
+        .max_by_key(|layout| layout.name.len())
+}
+
+/// `keymap` — это раскладка `name` или ее вариант
+fn name_matches(keymap: &str, name: &str) -> bool {
+    if keymap == name || keymap.starts_with(&format!("{name} (")) {
+        return true;
+    }
+    // `English (UK)` → `English (UK, extended)`
+    let open = name.strip_suffix(')').unwrap_or(name);
+    open.contains('(') && (keymap.starts_with(&format!("{open}, ")) || keymap.starts_with(&format!("{open})")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(keymap: &str) -> Option<&'static str> {
+        find_layout(keymap).map(|layout| layout.code)
+    }
+
+    #[test]
+    fn matches_real_active_keymaps() {
+        let cases = [
+            ("English (US)", "us"),
+            ("English (US, intl., with dead keys)", "us"),
+            ("English (US, euro on 5)", "us"),
+            ("English (Dvorak)", "us"),
+            ("English (Colemak)", "us"),
+            ("English (Colemak-DH)", "us"),
+            ("English (programmer Dvorak)", "us"),
+            ("English (Macintosh)", "us"),
+            ("English (intl., with AltGr dead keys)", "us"),
+            ("English (UK)", "gb"),
+            ("English (UK, extended, Windows)", "gb"),
+            ("English (UK, Colemak)", "gb"),
+            ("English (Australian)", "au"),
+            ("English (Canada)", "ca"),
+            ("English (India, with rupee)", "in"),
+            ("English (South Africa)", "za"),
+            ("Russian", "ru"),
+            ("Russian (phonetic)", "ru"),
+            ("Russian (phonetic, US)", "ru"),
+            ("Russian (typewriter)", "ru"),
+            ("Ukrainian", "ua"),
+            ("Ukrainian (phonetic)", "ua"),
+            ("Belarusian", "by"),
+            ("Belarusian (Latin)", "by"),
+            ("Kazakh", "kz"),
+            ("Kazakh (with Russian)", "kz"),
+            ("German", "de"),
+            ("German (no dead keys)", "de"),
+            ("German (Austria)", "at"),
+            ("German (Switzerland)", "ch"),
+            ("German (Switzerland, legacy)", "ch"),
+            ("French (Switzerland)", "ch"),
+            ("French", "fr"),
+            ("French (AZERTY, AFNOR)", "fr"),
+            ("French (BEPO)", "fr"),
+            ("French (Canada)", "ca"),
+            ("Belgian", "be"),
+            ("Spanish", "es"),
+            ("Spanish (Latin American)", "latam"),
+            ("Spanish (Latin American, dead tilde)", "latam"),
+            ("Catalan (Spain, with middle-dot L)", "es"),
+            ("Portuguese", "pt"),
+            ("Portuguese (Brazil)", "br"),
+            ("Portuguese (Brazil, ABNT2)", "br"),
+            ("Polish", "pl"),
+            ("Polish (Dvorak)", "pl"),
+            ("Czech (QWERTY)", "cz"),
+            ("Serbian (Latin)", "rs"),
+            ("Greek", "gr"),
+            ("Turkish (F)", "tr"),
+            ("Kurdish (Turkey, Latin Q)", "tr"),
+            ("Kurdish (Iraq, Arabic-Latin)", "iq"),
+            ("Hebrew", "il"),
+            ("Arabic", "ara"),
+            ("Arabic (Buckwalter)", "ara"),
+            ("Arabic (Morocco)", "ma"),
+            ("Persian", "ir"),
+            ("Japanese", "jp"),
+            ("Korean", "kr"),
+            ("Chinese", "cn"),
+            ("Vietnamese", "vn"),
+            ("Thai", "th"),
+            ("Hindi (Bolnagri)", "in"),
+            ("Swedish", "se"),
+            ("Norwegian", "no"),
+            ("Finnish", "fi"),
+            ("Danish", "dk"),
+            ("Estonian", "ee"),
+            ("Latvian", "lv"),
+            ("Lithuanian", "lt"),
+            ("Georgian", "ge"),
+            ("Armenian (phonetic)", "am"),
+            ("Esperanto", "epo"),
+        ];
+        for (keymap, expected) in cases {
+            assert_eq!(code(keymap), Some(expected), "{keymap}");
+        }
+    }
+
+    #[test]
+    fn matches_codes_and_variants() {
+        assert_eq!(code("us"), Some("us"));
+        assert_eq!(code("RU"), Some("ru"));
+        assert_eq!(code("us(intl)"), Some("us"));
+        assert_eq!(code("de(nodeadkeys)"), Some("de"));
+        assert_eq!(find_layout("ch").unwrap().name, "German (Switzerland)");
+    }
+
+    #[test]
+    fn does_not_match_by_substring() {
+        // Старое сопоставление по подстроке видело здесь `ru` или `us`
+        assert_eq!(code("Belarusian"), Some("by"));
+        assert_eq!(code("Pannonian Rusyn"), Some("rs"));
+        assert_eq!(code("Cherokee"), Some("us"));
+        assert_eq!(code("Englishman"), None);
+        assert_eq!(code("Klingon"), None);
+        assert_eq!(code(""), None);
+        assert_eq!(code("  "), None);
+    }
+
+    #[test]
+    fn builds_country_flags() {
+        assert_eq!(country_flag("RU"), "🇷🇺");
+        assert_eq!(country_flag("us"), "🇺🇸");
+        assert_eq!(find_layout("Ukrainian").unwrap().flag().as_deref(), Some("🇺🇦"));
+        assert_eq!(find_layout("Arabic").unwrap().flag(), None);
+    }
+
+    #[test]
+    fn table_is_consistent() {
+        for layout in LAYOUTS {
+            assert!(!layout.code.is_empty() && layout.code.chars().all(|c| c.is_ascii_lowercase()), "{layout:?}");
+            assert!(layout.country.is_none_or(|c| c.len() == 2 && c.chars().all(|c| c.is_ascii_uppercase())), "{layout:?}");
+            assert!(!layout.short.is_empty() && layout.short.chars().all(|c| c.is_ascii_uppercase()), "{layout:?}");
+            // Каждое название находит свою запись
+            assert_eq!(find_layout(layout.name).map(|found| found.name), Some(layout.name), "{layout:?}");
+        }
+    }
+}
@@ -3,6 +3,9 @@ use chrono::Local;
 /// Default pattern used when formatting the current local time.
 pub const DEFAULT_PATTERN: &str = "%a %d %b %H:%M";
 
+/// Pattern for narrow (vertical) bars: one component per line.
+pub const MULTILINE_PATTERN: &str = "%H\n%M\n%d\n%b";
+
 /// Returns the current local time formatted with the given chrono pattern.
 pub fn format_local(pattern: &str) -> String {
     Local::now().format(pattern).to_string()
@@ -24,6 +27,12 @@ mod tests {
         assert!(out.len() == 4);
     }
 
+    #[test]
+    fn formats_with_multiline_pattern() {
+        let out = format_local(MULTILINE_PATTERN);
+        assert_eq!(out.lines().count(), 4);
+    }
+
     #[test]
     fn formats_with_default_pattern() {
         let out = format_local_default();